@@ -0,0 +1,138 @@
+use crate::app::*;
+use crate::game::*;
+use crate::solver::*;
+
+/// How much a move risked, judged from what the board showed at the time.
+#[derive(Clone, Copy)]
+pub enum Class {
+    /// Every cell it opened was provably safe, or it was the click the mines
+    /// were placed around.
+    Safe,
+    /// Nothing on the board was provably safe, so some risk was unavoidable.
+    Guess(f64),
+    /// A risk taken while `safe` could have been opened instead.
+    Risk { probability: f64, safe: (usize, usize) },
+}
+
+/// A reveal or chord from the move history and its class.
+pub struct Reviewed {
+    /// One-based position in the move history.
+    pub index: usize,
+    pub mv: Move,
+    pub class: Class,
+    /// Whether the move hit a mine.
+    pub fatal: bool,
+}
+
+/// Walk a finished game's moves on the same layout and judge every move that
+/// opened cells. Flags are taken at their word, as the player saw them.
+pub fn review(game: &Game) -> Vec<Reviewed> {
+    let mut replay = game.retry();
+    let mut status = Status::Game;
+    let mut reviewed = Vec::new();
+    for (i, &mv) in game.moves.iter().enumerate() {
+        let opened = match mv.action {
+            Action::Flag => Vec::new(),
+            Action::Reveal => vec![(mv.x, mv.y)],
+            Action::Chord => replay
+                .neighbours(mv.x, mv.y)
+                .into_iter()
+                .filter(|&(x, y)| !replay.revealed(x, y) && !replay.flagged(x, y))
+                .collect(),
+        };
+        let class = if opened.is_empty() {
+            None
+        } else if game.safe_click() == Some(i) {
+            Some(Class::Safe)
+        } else {
+            Some(classify(&replay, &opened))
+        };
+        if replay.replay(mv, &mut status).is_err() {
+            continue;
+        }
+        if let Some(class) = class {
+            reviewed.push(Reviewed {
+                index: i + 1,
+                mv,
+                class,
+                fatal: matches!(status, Status::Failed),
+            });
+        }
+    }
+    reviewed
+}
+
+fn classify(game: &Game, opened: &[(usize, usize)]) -> Class {
+    let pos = Position::from_game(game);
+    let probs = probabilities(&pos);
+    // Cells the rules prove safe come first, then those only counting every
+    // arrangement shows to be empty.
+    let safe: Vec<(usize, usize)> = solve(&pos)
+        .into_iter()
+        .filter(|d| d.verdict == Verdict::Safe)
+        .map(|d| (d.x, d.y))
+        .chain(probs.safe())
+        .collect();
+    if opened.iter().all(|cell| safe.contains(cell)) {
+        return Class::Safe;
+    }
+
+    // A chord is as risky as the riskiest cell it opens.
+    let probability = opened
+        .iter()
+        .filter_map(|&(x, y)| probs.get(x, y))
+        .fold(0.0, f64::max);
+    match safe.first() {
+        Some(&safe) => Class::Risk { probability, safe },
+        None => Class::Guess(probability),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::*;
+
+    #[test]
+    fn daily_opening() {
+        let mode = Mode::Daily {
+            level: "easy".to_string(),
+            day: 20000,
+        };
+        let game = Game::with_opening(Config::easy(), mode, 12345);
+        let reviewed = review(&game);
+        assert_eq!(reviewed.len(), 1);
+        assert!(matches!(reviewed[0].class, Class::Safe));
+    }
+
+    /// The 1, 2, 1 along the middle of a 5x3 board with mines at 1,2 and 1,4,
+    /// then `last`. The mines can lie at 1,2 or 3,2 and at 1,4 or 3,4, so the
+    /// first, third and fifth columns are empty though no rule shows it.
+    fn after_121(last: (usize, usize)) -> Class {
+        let mines = [(0, 1), (0, 3)];
+        let mut game = Game::with_mines(Config::new(5, 3, 2), Mode::Custom, 0, &mines);
+        let mut status = Status::Game;
+        for (x, y) in [(1, 1), (1, 2), (1, 3), last] {
+            let mv = Move {
+                action: Action::Reveal,
+                x,
+                y,
+                time: 0,
+            };
+            game.replay(mv, &mut status).unwrap();
+        }
+        review(&game)[3].class
+    }
+
+    #[test]
+    fn exact_zero() {
+        assert!(matches!(after_121((0, 2)), Class::Safe));
+        assert!(matches!(
+            after_121((2, 1)),
+            Class::Risk {
+                safe: (0, 0),
+                ..
+            }
+        ));
+    }
+}
@@ -1,7 +1,28 @@
+use std::fs;
 use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use crate::analysis::{self, Reviewed};
+use crate::bot::{self, Bot};
+use crate::code::*;
+use crate::config::*;
+use crate::date;
+use crate::error::*;
 use crate::game::*;
 use crate::screen::*;
 use crate::input::*;
+use crate::journal;
+use crate::layout::*;
+use crate::metrics::*;
+use crate::preset::*;
+use crate::profile::{self, Settings};
+use crate::raw;
+use crate::replay::*;
+use crate::save::*;
+use crate::scores::*;
+use crate::share;
+use crate::solver::{self, Position};
+use crate::stats::*;
+use crate::viewer::*;
 
 #[derive(Clone, Copy)]
 pub enum Status {
@@ -9,6 +30,10 @@ pub enum Status {
     Game,
     Success,
     Failed,
+    Restore,
+    Replay,
+    Stats,
+    Scores,
 }
 
 pub struct App {
@@ -17,22 +42,399 @@ pub struct App {
     pub should_exit: bool,
     pub status: Status,
     pub input: Input,
+    pub presets: Presets,
+    pub profile: String,
+    pub settings: Settings,
+    pub stats: Stats,
+    pub viewer: Option<Viewer>,
+    pub scores: HighScores,
+    /// Best time in milliseconds before the last finished game.
+    pub previous_best: Option<u64>,
+    /// Rank of the last game in its high-score table.
+    pub last_rank: Option<usize>,
+    /// Share card of the last game, once asked for.
+    pub card: Option<String>,
+    /// Review of the last game's moves, once asked for.
+    pub review: Option<Vec<Reviewed>>,
+    /// The bot playing the current game, if it was asked to.
+    pub bot: Option<Bot>,
 }
 
 impl App {
     pub fn new() -> Self {
         let screen = Screen::new();
+        let profile = profile::current();
+        let status = match journal::recover(&profile) {
+            Ok(Some(_)) => Status::Restore,
+            _ => Status::Welcome,
+        };
         Self {
             screen,
             game: None,
             should_exit: false,
-            status: Status::Welcome,
+            status,
             input: Input::new(),
+            presets: Presets::load().unwrap_or_default(),
+            settings: Settings::load(&profile).unwrap_or_default(),
+            stats: Stats::load(&profile).unwrap_or_default(),
+            viewer: None,
+            scores: HighScores::load(&profile).unwrap_or_default(),
+            previous_best: None,
+            last_rank: None,
+            card: None,
+            review: None,
+            bot: None,
+            profile,
         }
     }
+
+    /// Name recorded with scores and exported replays.
+    pub fn player(&self) -> String {
+        if let Some(player) = &self.settings.player {
+            return player.clone();
+        }
+        if self.profile != profile::DEFAULT {
+            return self.profile.clone();
+        }
+        std::env::var("USER").unwrap_or_else(|_| "Anonymous".into())
+    }
+
+    /// Switch to another profile, creating it if needed.
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), AppError> {
+        profile::validate(name)?;
+        let settings = Settings::load(name)?;
+        settings.save(name)?;
+        self.stats = Stats::load(name)?;
+        self.scores = HighScores::load(name)?;
+        self.settings = settings;
+        self.profile = name.to_string();
+        profile::set_current(name)?;
+        Ok(())
+    }
+
+    pub fn start_game(&mut self, cfg: Config, mode: Mode) -> Result<(), AppError> {
+        let mut g = Game::new(cfg, mode);
+        g.generate();
+        self.begin(g)
+    }
+
+    /// Make `g` the current game. The game starts even if its journal can't be
+    /// written, the error only tells the player it won't survive a crash.
+    fn begin(&mut self, g: Game) -> Result<(), AppError> {
+        let journal = journal::start(&self.profile, &g.to_saved());
+        self.game = Some(g);
+        self.status = Status::Game;
+        journal.map_err(AppError::Journal)
+    }
+
+    /// Start today's board for a difficulty, only the first attempt of the day is ranked.
+    pub fn start_daily(&mut self, level: &str) -> Result<(), AppError> {
+        let cfg = match level {
+            "easy" => Config::easy(),
+            "normal" => Config::normal(),
+            "hard" => Config::hard(),
+            _ => return Err(AppError::UnknownCmd(level.into())),
+        };
+        let day = date::day();
+        let seed = daily_seed(level, day);
+        let mode = Mode::Daily {
+            level: level.to_string(),
+            day,
+        };
+        let mut g = Game::with_opening(cfg, mode, seed);
+        g.practice = !self.stats.start_daily(level, day);
+        self.stats.save(&self.profile)?;
+        self.begin(g)
+    }
+
+    /// Play the board behind a shared code.
+    pub fn play_code(&mut self, code: &str) -> Result<(), AppError> {
+        let g = BoardCode::decode(code)?.to_game();
+        self.begin(g)
+    }
+
+    /// Play the layout stored in a board file.
+    pub fn import_board(&mut self, path: &Path) -> Result<(), AppError> {
+        let layout = Layout::load(path)?;
+        let mut g = Game::with_mines(layout.config, Mode::Imported, 0, &layout.mines);
+        // The file lists every mine, so the result proves nothing.
+        g.practice = true;
+        self.begin(g)
+    }
+
+    pub fn export_board(&self, path: &Path) -> Result<(), AppError> {
+        let game = self.game.as_ref().ok_or(AppError::NoGame)?;
+        game.layout()?.save(path)?;
+        Ok(())
+    }
+
+    /// Write the replay being watched, or else the last game, as a RAW replay.
+    pub fn export_raw(&self, path: &Path) -> Result<(), AppError> {
+        let replay = match (&self.viewer, self.status) {
+            (Some(viewer), Status::Replay) => viewer.replay.clone(),
+            _ => Replay::from_game(self.game.as_ref().ok_or(AppError::NoGame)?),
+        };
+        fs::write(path, raw::export(&replay, &self.player())).map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    /// Play the last board again from the beginning.
+    pub fn retry(&mut self) -> Result<(), AppError> {
+        let g = self.game.as_ref().ok_or(AppError::NoGame)?.retry();
+        self.begin(g)
+    }
+
+    pub fn share(&mut self, ascii: bool) -> Result<(), AppError> {
+        let game = self.game.as_ref().ok_or(AppError::NoGame)?;
+        let won = matches!(self.status, Status::Success);
+        self.card = Some(share::card(game, won, ascii));
+        Ok(())
+    }
+
+    pub fn review(&mut self) -> Result<(), AppError> {
+        let game = self.game.as_ref().ok_or(AppError::NoGame)?;
+        self.review = Some(analysis::review(game));
+        Ok(())
+    }
+
+    /// Take back the move that lost the game and keep playing, for practice.
+    pub fn continue_game(&mut self) -> Result<(), AppError> {
+        if !matches!(self.status, Status::Failed) {
+            return Err(AppError::UnknownCmd("u".into()));
+        }
+        let g = self.game.as_ref().ok_or(AppError::NoGame)?.rewind();
+        self.begin(g)
+    }
+
+    /// Start a new board with the settings of the last game. After a daily
+    /// board that is an ordinary game of the same difficulty, and after an
+    /// imported or shared board a custom game of the same size.
+    pub fn new_game(&mut self) -> Result<(), AppError> {
+        let game = self.game.as_ref().ok_or(AppError::NoGame)?;
+        let mode = match &game.mode {
+            Mode::Daily { level, .. } => match level.as_str() {
+                "easy" => Mode::Easy,
+                "normal" => Mode::Normal,
+                _ => Mode::Hard,
+            },
+            Mode::Imported | Mode::Shared => Mode::Custom,
+            mode => mode.clone(),
+        };
+        self.start_game(game.config, mode)
+    }
+
+    /// Save the replay of the last game, as RAW if the file ends in `.raw`.
+    pub fn save_replay(&self, path: &Path) -> Result<(), AppError> {
+        if path.extension().is_some_and(|e| e == "raw") {
+            return self.export_raw(path);
+        }
+        let game = self.game.as_ref().ok_or(AppError::NoGame)?;
+        let replay = serde_json::to_string(&Replay::from_game(game)).map_err(StorageError::from)?;
+        fs::write(path, replay).map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    /// Apply a move typed by the player.
+    pub fn play(&mut self, input: &str) -> Result<(), AppError> {
+        let game = self.game.as_mut().ok_or(RenderError::NoGame).unwrap();
+        game.handle_enter(input, &mut self.status)?;
+        let logged = game
+            .moves
+            .last()
+            .map_or(Ok(()), |mv| journal::append(&self.profile, mv, game.clicks));
+        self.finish_game()?;
+        logged.map_err(AppError::Journal)
+    }
+
+    /// Show or hide mine probabilities, or show them with the probability of
+    /// one cell picked out.
+    pub fn toggle_overlay(&mut self, focus: Option<(usize, usize)>) -> Result<(), AppError> {
+        let game = self.game.as_mut().ok_or(AppError::NoGame)?;
+        game.overlay = focus.is_some() || !game.overlay;
+        game.focus = focus;
+        self.mark_assisted()
+    }
+
+    /// Point out the next cell to open.
+    pub fn hint(&mut self) -> Result<(), AppError> {
+        let game = self.game.as_mut().ok_or(AppError::NoGame)?;
+        if let Some(limit) = self.settings.hint_limit
+            && game.hints >= limit
+        {
+            return Err(AppError::NoHintsLeft(limit));
+        }
+        game.hint = solver::hint(&Position::from_game(game));
+        if game.hint.is_none() {
+            return Ok(());
+        }
+        game.hints += 1;
+        self.mark_assisted()
+    }
+
+    /// Let the bot play at the given speed, or stop it.
+    pub fn toggle_bot(&mut self, speed: Option<f64>) -> Result<(), AppError> {
+        self.game.as_ref().ok_or(AppError::NoGame)?;
+        self.bot = match (speed, &self.bot) {
+            (None, Some(_)) => None,
+            (speed, _) => Some(Bot::new(speed.unwrap_or(Bot::DEFAULT_SPEED))),
+        };
+        self.mark_assisted()
+    }
+
+    /// Keep the current game out of statistics and high scores, for help from
+    /// the solver. The journal starts over from the game as it stands so that
+    /// a restored game stays assisted, with its hint count.
+    fn mark_assisted(&mut self) -> Result<(), AppError> {
+        let game = self.game.as_mut().ok_or(AppError::NoGame)?;
+        game.assisted = true;
+        journal::start(&self.profile, &game.to_saved()).map_err(AppError::Journal)
+    }
+
+    /// Make the bot's next move.
+    fn step_bot(&mut self) -> Result<(), AppError> {
+        let game = self.game.as_mut().ok_or(AppError::NoGame)?;
+        let Some(step) = bot::plan(game).into_iter().next() else {
+            self.bot = None;
+            return Ok(());
+        };
+        game.apply(step.action, step.x, step.y, &mut self.status)?;
+        let logged = game
+            .moves
+            .last()
+            .map_or(Ok(()), |mv| journal::append(&self.profile, mv, game.clicks));
+        self.finish_game()?;
+        logged.map_err(AppError::Journal)
+    }
+
+    /// Save the game in progress and go back to the menu.
+    pub fn quit_game(&mut self) -> Result<(), AppError> {
+        self.bot = None;
+        self.save_game()?;
+        journal::clear(&self.profile)?;
+        self.status = Status::Welcome;
+        Ok(())
+    }
+
+    /// Save the current game if it is still in progress.
+    pub fn save_game(&self) -> Result<(), AppError> {
+        if let (Some(game), Status::Game) = (&self.game, self.status)
+            && game.started()
+        {
+            game.to_saved().save(&self.profile)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_game(&mut self) -> Result<(), AppError> {
+        let saved = SavedGame::take(&self.profile)?.ok_or(AppError::NoSave)?;
+        self.begin(Game::from_saved(saved))
+    }
+
+    /// Rebuild the game left behind by a crash from the journal.
+    pub fn restore_game(&mut self) -> Result<(), AppError> {
+        let (state, moves) = journal::recover(&self.profile)?.ok_or(AppError::NoSave)?;
+        let mut game = Game::from_saved(state);
+        let mut status = Status::Game;
+        for mv in moves {
+            game.replay(mv, &mut status)?;
+        }
+        self.game = Some(game);
+        self.status = status;
+        Ok(())
+    }
+
+    /// Open a replay file, or the most recent replay if no path is given.
+    pub fn open_replay(&mut self, path: Option<&str>) -> Result<(), AppError> {
+        let path = match path {
+            Some(p) => PathBuf::from(p),
+            None => Replay::list(&self.profile)?.pop().ok_or(AppError::NoReplay)?,
+        };
+        self.viewer = Some(Viewer::new(Replay::open(&path)?));
+        self.status = Status::Replay;
+        Ok(())
+    }
+
+    /// Whether the main loop has to wake up without input.
+    pub fn ticking(&self) -> bool {
+        match self.status {
+            Status::Replay => self.viewer.as_ref().is_some_and(|v| v.playing),
+            Status::Game => self.bot.is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(viewer) = self.viewer.as_mut() {
+            viewer.tick();
+        }
+        if let (Status::Game, Some(bot)) = (self.status, self.bot.as_mut())
+            && bot.due()
+            && let Err(e) = self.step_bot()
+        {
+            self.bot = None;
+            self.input.error_msg = format!("{}", e).into();
+        }
+    }
+
+    /// Record the result of the current game once it is won or lost.
+    pub fn finish_game(&mut self) -> Result<(), AppError> {
+        let Some(game) = &self.game else {
+            return Ok(());
+        };
+        let won = match self.status {
+            Status::Success => true,
+            Status::Failed => false,
+            _ => return Ok(()),
+        };
+        self.previous_best = self.stats.get(&game.key()).and_then(|s| s.best);
+        self.last_rank = None;
+        self.card = None;
+        self.review = None;
+        self.bot = None;
+        if game.counted() {
+            let dura = game.elapsed();
+            let speed = bbbv_per_second(game.metrics().bbbv, dura.as_secs_f64());
+            self.stats.record(&game.key(), won, dura, speed);
+            if let Mode::Daily { level, day } = &game.mode {
+                self.stats.record_daily(level, *day, won);
+            }
+            self.stats.save(&self.profile)?;
+            if won {
+                let score = Score {
+                    time: dura.as_millis() as u64,
+                    bbbv_per_second: speed,
+                    date: date::today(),
+                    player: self.player(),
+                    seed: game.seed,
+                };
+                self.last_rank = self.scores.insert(&game.key(), score);
+                self.scores.save(&self.profile)?;
+            }
+        }
+        Replay::from_game(game).save(&self.profile)?;
+        SavedGame::discard(&self.profile, game.seed)?;
+        journal::clear(&self.profile)?;
+        Ok(())
+    }
     
     pub fn print(&self, s: &str) {
         self.screen.print(s.to_string()).unwrap();
         stdout().flush().unwrap();
     }
 }
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Seed shared by everyone playing the daily board, FNV-1a of the day and level.
+fn daily_seed(level: &str, day: i64) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in format!("{}:{}", date::format_day(day), level).bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
@@ -0,0 +1,92 @@
+//! Plays seeded games with the built-in bot at each difficulty and reports
+//! how it did, so solver and generator changes can be compared.
+//!
+//! ```text
+//! cargo run --release --bin bench -- [games per difficulty] [first seed]
+//! ```
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use t_minesweeper::app::Status;
+use t_minesweeper::bot;
+use t_minesweeper::config::*;
+use t_minesweeper::game::*;
+
+#[derive(Default)]
+struct Results {
+    games: u32,
+    won: u32,
+    guesses: u32,
+    bbbv: usize,
+    time: Duration,
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let games = args.next().map_or(Ok(1000), |s| s.parse::<u32>());
+    let seed = args.next().map_or(Ok(0), |s| s.parse::<u64>());
+    let (Ok(games), Ok(seed)) = (games, seed) else {
+        eprintln!("usage: bench [games per difficulty] [first seed]");
+        std::process::exit(2);
+    };
+
+    println!(
+        "{:<8} {:>6} {:>6} {:>7} {:>13} {:>8} {:>10}",
+        "Mode", "Games", "Won", "Win%", "Guesses/game", "Avg 3BV", "Time/game"
+    );
+    let start = Instant::now();
+    for (name, cfg, mode) in [
+        ("easy", Config::easy(), Mode::Easy),
+        ("normal", Config::normal(), Mode::Normal),
+        ("hard", Config::hard(), Mode::Hard),
+    ] {
+        let mut r = Results::default();
+        for i in 0..games as u64 {
+            play(cfg, mode.clone(), seed + i, &mut r);
+        }
+        let per_game = |n: f64| n / r.games.max(1) as f64;
+        println!(
+            "{:<8} {:>6} {:>6} {:>6.1}% {:>13.2} {:>8.1} {:>8.2}ms",
+            name,
+            r.games,
+            r.won,
+            per_game(r.won as f64) * 100.0,
+            per_game(r.guesses as f64),
+            per_game(r.bbbv as f64),
+            per_game(r.time.as_secs_f64() * 1000.0),
+        );
+    }
+    println!("Total runtime: {:.2}s", start.elapsed().as_secs_f64());
+}
+
+/// Let the bot play one game to the end. The first click is protected, so
+/// only later moves that risk a mine count as guesses.
+fn play(cfg: Config, mode: Mode, seed: u64, r: &mut Results) {
+    let start = Instant::now();
+    let mut game = Game::new(cfg, mode);
+    game.seed = seed;
+    let mut status = Status::Game;
+    while let Status::Game = status {
+        let steps = bot::plan(&game);
+        if steps.is_empty() {
+            break;
+        }
+        for step in steps {
+            if step.risk > 0.0 && game.started() {
+                r.guesses += 1;
+            }
+            // Earlier reveals in the batch may already have opened the cell.
+            let _ = game.apply(step.action, step.x, step.y, &mut status);
+            if !matches!(status, Status::Game) {
+                break;
+            }
+        }
+    }
+    r.games += 1;
+    if let Status::Success = status {
+        r.won += 1;
+    }
+    r.bbbv += game.metrics().bbbv;
+    r.time += start.elapsed();
+}
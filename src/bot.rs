@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use crate::game::*;
+use crate::solver::*;
+
+/// A move chosen by the bot.
+#[derive(Clone, Copy)]
+pub struct Step {
+    pub action: Action,
+    pub x: usize,
+    pub y: usize,
+    /// Chance of hitting a mine, zero for moves the solver proved.
+    pub risk: f64,
+}
+
+/// The moves that follow from the board: flags on every proven mine and
+/// reveals of every proven safe cell, or else one reveal of the cell least
+/// likely to be a mine.
+pub fn plan(game: &Game) -> Vec<Step> {
+    let pos = Position::from_game(game);
+    let mut steps: Vec<Step> = solve(&pos)
+        .into_iter()
+        .filter(|d| d.verdict == Verdict::Safe || !game.flagged(d.x, d.y))
+        .map(|d| Step {
+            action: match d.verdict {
+                Verdict::Safe => Action::Reveal,
+                Verdict::Mine => Action::Flag,
+            },
+            x: d.x,
+            y: d.y,
+            risk: 0.0,
+        })
+        .collect();
+    steps.sort_by_key(|s| s.action != Action::Flag);
+    if steps.is_empty()
+        && let Some((x, y, risk)) = probabilities(&pos).safest()
+    {
+        steps.push(Step {
+            action: Action::Reveal,
+            x,
+            y,
+            risk,
+        });
+    }
+    steps
+}
+
+/// The bot playing the current game on screen.
+pub struct Bot {
+    /// Moves per second.
+    pub speed: f64,
+    last_move: Instant,
+}
+
+impl Bot {
+    pub const DEFAULT_SPEED: f64 = 4.0;
+
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            last_move: Instant::now(),
+        }
+    }
+
+    /// Whether it is time for the next move, which is then counted as made.
+    pub fn due(&mut self) -> bool {
+        let now = Instant::now();
+        if now - self.last_move < Duration::from_secs_f64(1.0 / self.speed) {
+            return false;
+        }
+        self.last_move = now;
+        true
+    }
+}
@@ -0,0 +1,370 @@
+use crate::app::Status;
+use crate::config::*;
+use crate::error::{CodeError, GameError};
+use crate::game::*;
+
+/// Version 2 places seeded mines with ChaCha8, the same on every platform and
+/// `rand` release; version 1 codes no longer give the board they were made for.
+const VERSION: u8 = 2;
+/// Largest width or height a code may describe.
+const MAX_SIDE: usize = 256;
+const PREFIX: &str = "TM-";
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Everything needed to play a board again, in the form of a short text code.
+///
+/// The code is `TM-` followed by the base32 of a version byte, the board, and a
+/// 32-bit checksum of both.
+pub enum BoardCode {
+    /// Mines placed from the seed around the first click, which is revealed.
+    Seeded {
+        config: Config,
+        seed: u64,
+        first: (usize, usize),
+    },
+    /// Mines placed from the seed around an opening in the centre.
+    Opening { config: Config, seed: u64 },
+    Layout {
+        config: Config,
+        mines: Vec<(usize, usize)>,
+    },
+}
+
+impl BoardCode {
+    /// The shortest code that reproduces the game's mines.
+    pub fn from_game(game: &Game) -> Result<Self, GameError> {
+        let layout = game.layout()?;
+        let config = game.config;
+        let seed = game.seed;
+
+        let opening = BoardCode::Opening { config, seed };
+        if matches!(game.mode, Mode::Daily { .. }) && opening.to_game().mines() == layout.mines {
+            return Ok(opening);
+        }
+        let first = game
+            .moves
+            .iter()
+            .find(|mv| mv.action == Action::Reveal)
+            .map(|mv| (mv.x, mv.y));
+        if let Some(first) = first {
+            let seeded = BoardCode::Seeded {
+                config,
+                seed,
+                first,
+            };
+            if seeded.to_game().mines() == layout.mines {
+                return Ok(seeded);
+            }
+        }
+        Ok(BoardCode::Layout {
+            config,
+            mines: layout.mines,
+        })
+    }
+
+    pub fn to_game(&self) -> Game {
+        match self {
+            BoardCode::Seeded {
+                config,
+                seed,
+                first,
+            } => {
+                let mut game = Game::new(*config, Mode::Shared);
+                game.seed = *seed;
+                let mv = Move {
+                    action: Action::Reveal,
+                    x: first.0,
+                    y: first.1,
+                    time: 0,
+                };
+                let _ = game.replay(mv, &mut Status::Game);
+                game
+            }
+            BoardCode::Opening { config, seed } => Game::with_opening(*config, Mode::Shared, *seed),
+            BoardCode::Layout { config, mines } => {
+                // Anyone holding the code can read the mines off it.
+                let mut game = Game::with_mines(*config, Mode::Shared, 0, mines);
+                game.practice = true;
+                game
+            }
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![VERSION];
+        let push_config = |bytes: &mut Vec<u8>, cfg: &Config| {
+            bytes.extend((cfg.col as u16).to_be_bytes());
+            bytes.extend((cfg.row as u16).to_be_bytes());
+            bytes.extend((cfg.mine as u16).to_be_bytes());
+        };
+        match self {
+            BoardCode::Seeded {
+                config,
+                seed,
+                first,
+            } => {
+                bytes.push(0);
+                push_config(&mut bytes, config);
+                bytes.extend(seed.to_be_bytes());
+                bytes.extend((first.0 as u16).to_be_bytes());
+                bytes.extend((first.1 as u16).to_be_bytes());
+            }
+            BoardCode::Opening { config, seed } => {
+                bytes.push(1);
+                push_config(&mut bytes, config);
+                bytes.extend(seed.to_be_bytes());
+            }
+            BoardCode::Layout { config, mines } => {
+                bytes.push(2);
+                push_config(&mut bytes, config);
+                let mut bits = vec![0u8; (config.col * config.row).div_ceil(8)];
+                for &(x, y) in mines {
+                    let i = x * config.col + y;
+                    bits[i / 8] |= 1 << (i % 8);
+                }
+                bytes.extend(bits);
+            }
+        }
+        bytes.extend(checksum(&bytes).to_be_bytes());
+        format!("{}{}", PREFIX, base32_encode(&bytes))
+    }
+
+    pub fn decode(code: &str) -> Result<Self, CodeError> {
+        let code = code.trim().to_uppercase();
+        let body = code.strip_prefix(PREFIX).ok_or(CodeError::NotACode)?;
+        let bytes = base32_decode(body)?;
+        if bytes.len() < 6 {
+            return Err(CodeError::TooShort);
+        }
+        let (data, sum) = bytes.split_at(bytes.len() - 4);
+        if checksum(data).to_be_bytes() != sum {
+            return Err(CodeError::Checksum);
+        }
+        if data[0] != VERSION {
+            return Err(CodeError::Version(data[0]));
+        }
+
+        let mut reader = Reader { data, pos: 1 };
+        let kind = reader.u8()?;
+        let config = Config::new(reader.u16()? as usize, reader.u16()? as usize, reader.u16()? as i32);
+        if config.col == 0
+            || config.row == 0
+            || config.col > MAX_SIDE
+            || config.row > MAX_SIDE
+            || config.mine as usize >= config.col * config.row
+        {
+            return Err(CodeError::InvalidBoard);
+        }
+        let board = match kind {
+            0 => {
+                let seed = reader.u64()?;
+                let first = (reader.u16()? as usize, reader.u16()? as usize);
+                if first.0 >= config.row || first.1 >= config.col {
+                    return Err(CodeError::InvalidBoard);
+                }
+                BoardCode::Seeded {
+                    config,
+                    seed,
+                    first,
+                }
+            }
+            1 => {
+                // The opening is the centre 3x3, clipped to the board.
+                let opening = config.col.min(3) * config.row.min(3);
+                if config.mine as usize > config.col * config.row - opening {
+                    return Err(CodeError::InvalidBoard);
+                }
+                BoardCode::Opening {
+                    config,
+                    seed: reader.u64()?,
+                }
+            }
+            2 => {
+                let cells = config.col * config.row;
+                let bits = reader.take(cells.div_ceil(8))?;
+                let mines: Vec<(usize, usize)> = (0..cells)
+                    .filter(|i| bits[i / 8] & (1 << (i % 8)) != 0)
+                    .map(|i| (i / config.col, i % config.col))
+                    .collect();
+                if mines.len() != config.mine as usize {
+                    return Err(CodeError::InvalidBoard);
+                }
+                BoardCode::Layout { config, mines }
+            }
+            _ => return Err(CodeError::InvalidBoard),
+        };
+        if reader.pos != data.len() {
+            return Err(CodeError::InvalidBoard);
+        }
+        Ok(board)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodeError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or(CodeError::TooShort)?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, CodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, CodeError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, CodeError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+/// FNV-1a, enough to catch typos and truncated codes.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for &b in bytes {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &b in bytes {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Result<Vec<u8>, CodeError> {
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.chars() {
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or(CodeError::InvalidChar(c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn round_trip(code: BoardCode) {
+        let text = code.encode();
+        let decoded = BoardCode::decode(&text).unwrap();
+        assert_eq!(decoded.encode(), text);
+        assert_eq!(decoded.to_game().mines(), code.to_game().mines());
+    }
+
+    /// A code whose bytes after the version are `data`, with a valid checksum.
+    fn raw_code(version: u8, data: &[u8]) -> String {
+        let mut bytes = vec![version];
+        bytes.extend(data);
+        bytes.extend(checksum(&bytes).to_be_bytes());
+        format!("{}{}", PREFIX, base32_encode(&bytes))
+    }
+
+    fn error(code: &str) -> CodeError {
+        match BoardCode::decode(code) {
+            Ok(_) => panic!("{} decoded", code),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let config = Config::new(9, 7, 10);
+        round_trip(BoardCode::Seeded {
+            config,
+            seed: 42,
+            first: (3, 5),
+        });
+        round_trip(BoardCode::Opening { config, seed: 42 });
+        round_trip(BoardCode::Layout {
+            config: Config::new(9, 7, 3),
+            mines: vec![(0, 0), (2, 8), (6, 3)],
+        });
+    }
+
+    #[test]
+    fn flipped_character() {
+        let code = BoardCode::Opening {
+            config: Config::easy(),
+            seed: 7,
+        }
+        .encode();
+        let i = PREFIX.len() + 4;
+        let flipped = if &code[i..=i] == "A" { "B" } else { "A" };
+        let code = format!("{}{}{}", &code[..i], flipped, &code[i + 1..]);
+        assert!(matches!(error(&code), CodeError::Checksum));
+    }
+
+    #[test]
+    fn truncated() {
+        let code = BoardCode::Opening {
+            config: Config::easy(),
+            seed: 7,
+        }
+        .encode();
+        assert!(matches!(error(&code[..PREFIX.len() + 8]), CodeError::TooShort));
+        assert!(matches!(error("TM-"), CodeError::TooShort));
+    }
+
+    #[test]
+    fn wrong_version() {
+        let mut data = vec![1, 0, 8, 0, 8, 0, 10];
+        data.extend(7u64.to_be_bytes());
+        assert_eq!(
+            AppError::from(error(&raw_code(1, &data))).to_string(),
+            "Invalid board code: unsupported version 1"
+        );
+        assert!(BoardCode::decode(&raw_code(VERSION, &data)).is_ok());
+        assert!(matches!(error("AB-CDEF"), CodeError::NotACode));
+    }
+
+    #[test]
+    fn impossible_boards() {
+        let huge = BoardCode::Opening {
+            config: Config::new(MAX_SIDE + 1, 2, 1),
+            seed: 7,
+        };
+        assert!(matches!(error(&huge.encode()), CodeError::InvalidBoard));
+        // Nine cells, all of them in the opening.
+        let crowded = BoardCode::Opening {
+            config: Config::new(3, 3, 1),
+            seed: 7,
+        };
+        assert!(matches!(error(&crowded.encode()), CodeError::InvalidBoard));
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Config {
     pub col: usize,
     pub row: usize,
@@ -10,7 +12,7 @@ impl Config {
         Self {
             col: c,
             row: r,
-            mine: mine,
+            mine,
         }
     }
 
@@ -36,3 +38,33 @@ impl Config {
         }
     }
 }
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Mode {
+    Easy,
+    Normal,
+    Hard,
+    Custom,
+    Imported,
+    /// A board played from a shared code.
+    Shared,
+    Preset(String),
+    /// The board of the day for a difficulty, `day` counts from 1970-01-01.
+    Daily { level: String, day: i64 },
+}
+
+impl Mode {
+    /// Key used to group statistics, see `Game::key` for custom sizes.
+    pub fn key(&self) -> String {
+        match self {
+            Mode::Easy => "easy".into(),
+            Mode::Normal => "normal".into(),
+            Mode::Hard => "hard".into(),
+            Mode::Custom => "custom".into(),
+            Mode::Imported => "imported".into(),
+            Mode::Shared => "shared".into(),
+            Mode::Preset(name) => format!("preset:{}", name),
+            Mode::Daily { level, .. } => format!("daily:{}", level),
+        }
+    }
+}
@@ -16,6 +16,12 @@ pub enum GameError {
     ParseIntErr(#[from] ParseIntError),
     #[error("Already exploded")]
     AlreadyExploded,
+    #[error("Cell is flagged")]
+    Flagged,
+    #[error("Board is not generated yet")]
+    NotGenerated,
+    #[error("Nothing to chord")]
+    InvalidChord,
 }
 
 #[derive(Error, Debug)]
@@ -26,4 +32,92 @@ pub enum AppError {
     ParseIntErr(#[from] ParseIntError),
     #[error("Invalid custom size")]
     InvalidCustom,
+    #[error("No saved game")]
+    NoSave,
+    #[error("No replay")]
+    NoReplay,
+    #[error("No game")]
+    NoGame,
+    #[error("No hints left, the limit is {0} per game")]
+    NoHintsLeft(u32),
+    #[error("Invalid name: {0}")]
+    InvalidName(Arc<str>),
+    #[error("Unknown preset: {0}")]
+    UnknownPreset(Arc<str>),
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("Crash recovery is off for this game: {0}")]
+    Journal(StorageError),
+    #[error("Invalid board: {0}")]
+    Layout(#[from] LayoutError),
+    #[error("Invalid replay: {0}")]
+    Replay(#[from] ReplayError),
+    #[error("Invalid board code: {0}")]
+    Code(#[from] CodeError),
+}
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("No data directory")]
+    NoDataDir,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid data: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum LayoutError {
+    #[error("empty board")]
+    Empty,
+    #[error("invalid header")]
+    InvalidHeader,
+    #[error("header does not match the board")]
+    HeaderMismatch,
+    #[error("{0} mines do not fit the board")]
+    MineCount(i32),
+    #[error("invalid character '{0}' on row {1}")]
+    InvalidChar(char, usize),
+    #[error("row {0} has a different width")]
+    RaggedRows(usize),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("missing {0}")]
+    MissingField(&'static str),
+    #[error("invalid board")]
+    InvalidBoard,
+    #[error("invalid line {0}")]
+    InvalidLine(usize),
+    #[error("not an {0} replay")]
+    NotA(&'static str),
+    #[error("unknown event {0} at byte {1}")]
+    InvalidEvent(u8, usize),
+    #[error("file is cut off")]
+    Truncated,
+    #[error("{0}")]
+    Storage(#[from] StorageError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum CodeError {
+    #[error("missing TM- prefix")]
+    NotACode,
+    #[error("invalid character '{0}'")]
+    InvalidChar(char),
+    #[error("code is cut off")]
+    TooShort,
+    #[error("checksum mismatch, the code is corrupted")]
+    Checksum,
+    #[error("unsupported version {0}")]
+    Version(u8),
+    #[error("invalid board")]
+    InvalidBoard,
 }
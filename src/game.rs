@@ -1,13 +1,19 @@
 #![allow(dead_code)]
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::app::*;
 use crate::config::*;
 use crate::error::GameError;
 use crate::screen::*;
+use crate::layout::*;
+use crate::metrics::*;
+use crate::save::*;
+use crate::solver::*;
 use crossterm::style::Stylize;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy)]
 enum Item {
@@ -16,30 +22,217 @@ enum Item {
     Number(i8),
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Reveal,
+    Flag,
+    Chord,
+}
+
+/// A move applied to the board, `time` is the elapsed milliseconds.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Move {
+    pub action: Action,
+    pub x: usize,
+    pub y: usize,
+    pub time: u64,
+}
+
 pub struct Game {
     pub start: Instant,
+    /// Time played before `start`, e.g. in a previous session.
+    offset: Duration,
     first: bool,
+    /// Index in `moves` of the reveal the mines were placed around.
+    safe_click: Option<usize>,
     pub config: Config,
+    pub mode: Mode,
+    pub seed: u64,
     world: Vec<Vec<Item>>,
     board: Vec<Vec<bool>>,
+    flags: Vec<Vec<bool>>,
+    pub moves: Vec<Move>,
+    pub clicks: Clicks,
+    /// Elapsed time when the game was won or lost.
+    finished: Option<Duration>,
+    /// Practice games don't count toward statistics.
+    pub practice: bool,
+    /// Games played with help from the solver don't count either.
+    pub assisted: bool,
+    /// Colour hidden cells by their mine probability.
+    pub overlay: bool,
+    /// The cell whose probability is shown, highlighted on the board.
+    pub focus: Option<(usize, usize)>,
+    /// The last hint given, until the next move.
+    pub hint: Option<Hint>,
+    pub hints: u32,
     pub draw_mine: bool,
 }
 
 impl Game {
-    pub fn new(cfg: Config) -> Self {
+    pub fn new(cfg: Config, mode: Mode) -> Self {
         // init world
         let world: Vec<Vec<Item>> = vec![vec![Item::Space; cfg.col]; cfg.row];
         // init screen
         // init board
         let board: Vec<Vec<bool>> = vec![vec![false; cfg.col]; cfg.row];
+        let flags: Vec<Vec<bool>> = vec![vec![false; cfg.col]; cfg.row];
 
         Self {
             start: Instant::now(),
+            offset: Duration::ZERO,
             first: true,
+            safe_click: None,
             config: cfg,
-            world: world,
-            board: board,
+            mode,
+            seed: rand::rng().random(),
+            world,
+            board,
+            flags,
+            moves: Vec::new(),
+            clicks: Clicks::default(),
+            finished: None,
+            practice: false,
+            assisted: false,
+            overlay: false,
+            focus: None,
+            hint: None,
+            hints: 0,
+            draw_mine: false,
+        }
+    }
+
+    /// A game on a fixed layout, the first click is not protected.
+    pub fn with_mines(cfg: Config, mode: Mode, seed: u64, mines: &[(usize, usize)]) -> Self {
+        let mut game = Self::new(cfg, mode);
+        game.seed = seed;
+        game.first = false;
+        for &(x, y) in mines {
+            game.world[x][y] = Item::Mine;
+        }
+        game.generate_number();
+        game
+    }
+
+    /// A board fixed by its seed alone, with an opening in the centre that is
+    /// revealed for the player.
+    pub fn with_opening(cfg: Config, mode: Mode, seed: u64) -> Self {
+        let mut game = Self::new(cfg, mode);
+        game.seed = seed;
+        game.first = false;
+        game.safe_click = Some(0);
+        let (cx, cy) = (cfg.row / 2, cfg.col / 2);
+        let mut safe = game.neighbours(cx, cy);
+        safe.push((cx, cy));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mines = (cfg.mine.max(0) as usize).min(cfg.col * cfg.row - safe.len());
+        let mut i = 0;
+        while i < mines {
+            let x = rng.random_range(0..cfg.row);
+            let y = rng.random_range(0..cfg.col);
+            if !safe.contains(&(x, y)) && matches!(game.world[x][y], Item::Space) {
+                game.world[x][y] = Item::Mine;
+                i += 1;
+            }
+        }
+        game.generate_number();
+
+        let mut status = Status::Game;
+        let start = Move {
+            action: Action::Reveal,
+            x: cx,
+            y: cy,
+            time: 0,
+        };
+        let _ = game.replay(start, &mut status);
+        game
+    }
+
+    pub fn from_saved(saved: SavedGame) -> Self {
+        let SavedGame {
+            config,
+            mode,
+            seed,
+            first,
+            safe_click,
+            mines,
+            board,
+            flags,
+            elapsed,
+            moves,
+            clicks,
+            practice,
+            assisted,
+            hints,
+        } = saved;
+        let mut world = vec![vec![Item::Space; config.col]; config.row];
+        for (x, y) in mines {
+            world[x][y] = Item::Mine;
+        }
+
+        let mut game = Self {
+            start: Instant::now(),
+            offset: Duration::from_millis(elapsed),
+            first,
+            safe_click,
+            config,
+            mode,
+            seed,
+            world,
+            board,
+            flags,
+            moves,
+            clicks,
+            finished: None,
+            practice,
+            assisted,
+            overlay: false,
+            focus: None,
+            hint: None,
+            hints,
             draw_mine: false,
+        };
+        game.generate_number();
+        game
+    }
+
+    /// Positions of all mines, as `(row, col)`.
+    pub fn mines(&self) -> Vec<(usize, usize)> {
+        let mut mines = Vec::new();
+        for (i, line) in self.world.iter().enumerate() {
+            for (j, item) in line.iter().enumerate() {
+                if let Item::Mine = item {
+                    mines.push((i, j));
+                }
+            }
+        }
+        mines
+    }
+
+    pub fn layout(&self) -> Result<Layout, GameError> {
+        if self.first {
+            return Err(GameError::NotGenerated);
+        }
+        Ok(Layout::new(self.config, self.mines()))
+    }
+
+    pub fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            config: self.config,
+            mode: self.mode.clone(),
+            seed: self.seed,
+            first: self.first,
+            safe_click: self.safe_click,
+            mines: self.mines(),
+            board: self.board.clone(),
+            flags: self.flags.clone(),
+            elapsed: self.elapsed().as_millis() as u64,
+            moves: self.moves.clone(),
+            clicks: self.clicks,
+            practice: self.practice,
+            assisted: self.assisted,
+            hints: self.hints,
         }
     }
 
@@ -48,10 +241,74 @@ impl Game {
         self.generate_number();
     }
 
+    pub fn elapsed(&self) -> Duration {
+        self.finished
+            .unwrap_or_else(|| self.offset + self.start.elapsed())
+    }
+
+    pub fn metrics(&self) -> BoardMetrics {
+        analyse(&self.config, &self.mines())
+    }
+
+    /// 3BV cleared so far.
+    pub fn bbbv_cleared(&self) -> usize {
+        cleared(&self.config, &self.mines(), &self.board)
+    }
+
+    /// The position just before the last move, as a practice game.
+    pub fn rewind(&self) -> Self {
+        let mut game = self.retry();
+        let mut status = Status::Game;
+        let count = self.moves.len().saturating_sub(1);
+        for &mv in &self.moves[..count] {
+            let _ = game.replay(mv, &mut status);
+        }
+        game.clicks = self.clicks;
+        game.safe_click = self.safe_click;
+        game
+    }
+
+    /// A fresh game on the same layout, as a practice game since the layout
+    /// is already known.
+    pub fn retry(&self) -> Self {
+        let mut game = Self::with_mines(self.config, self.mode.clone(), self.seed, &self.mines());
+        game.practice = true;
+        game
+    }
+
+    /// The move that placed the mines around the cell it opened, so it could
+    /// not hit one, or the opening revealed for the player. Other boards laid
+    /// out in advance have none.
+    pub fn safe_click(&self) -> Option<usize> {
+        self.safe_click
+    }
+
+    /// Key the game's statistics and high scores are kept under. Custom games
+    /// are told apart by size, so a tiny board can't stand in for a large one.
+    pub fn key(&self) -> String {
+        let Config { col, row, mine } = self.config;
+        match self.mode {
+            Mode::Custom => format!("custom:{}x{}/{}", col, row, mine),
+            _ => self.mode.key(),
+        }
+    }
+
+    /// Whether the result goes into statistics and high scores.
+    pub fn counted(&self) -> bool {
+        !self.practice && !self.assisted
+    }
+
+    /// Whether any move has been made yet.
+    pub fn started(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
     pub fn draw(&self, all: bool, screen: &Screen) {
         let cfg = &self.config;
         let world = &self.world;
         let board = &self.board;
+        let flags = &self.flags;
+        let probs = (self.overlay && !all).then(|| probabilities(&Position::from_game(self)));
 
         let mut h: String = if cfg.col < 10 {
             "   ".to_string()
@@ -88,13 +345,27 @@ impl Game {
             line.push(' ');
 
             for j in 0..cfg.col {
-                let c;
-                if !board[i][j] && !all {
-                    c = '·';
+                let c = if flags[i][j] && !board[i][j] && !all {
+                    'F'
+                } else if !board[i][j] && !all {
+                    '·'
                 } else {
-                    c = world[i][j].render();
+                    world[i][j].render()
+                };
+                let mut color_c = match &probs {
+                    Some(probs) if c == '·' => probability_color(c, probs.get(i, j)),
+                    _ => render_color(c),
+                };
+                if probs.is_some() && self.focus == Some((i, j)) {
+                    color_c = color_c.reverse().to_string();
+                }
+                if let Some(hint) = &self.hint {
+                    if (hint.x, hint.y) == (i, j) {
+                        color_c = c.to_string().black().on_green().to_string();
+                    } else if hint.deduction.as_ref().is_some_and(|d| d.involved().contains(&(i, j))) {
+                        color_c = c.to_string().black().on_yellow().to_string();
+                    }
                 }
-                let color_c = render_color(c);
                 line += color_c.as_str();
                 line.push(' ');
                 line.push(' ');
@@ -108,7 +379,7 @@ impl Game {
 
     fn generate_mine(&mut self) {
         let Config { col, row, mine } = self.config;
-        let mut rng = rand::rng();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
         let mut i = 0;
         while i < mine {
             let rd_col = rng.random_range(0..col);
@@ -125,7 +396,7 @@ impl Game {
 
     fn generate_mine_by_pos(&mut self, p_col: usize, p_row: usize) {
         let Config { col, row, mine } = self.config;
-        let mut rng = rand::rng();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
         let mut rd_col;
         let mut rd_row;
 
@@ -174,45 +445,118 @@ impl Game {
 
     pub fn handle_enter(
         &mut self,
-        input: &String,
+        input: &str,
         status: &mut Status,
     ) -> Result<(), GameError> {
-        let cfg = self.config;
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
+        let parts: Vec<&str> = input.split_whitespace().collect();
 
+        let (action, parts) = match parts.as_slice() {
+            ["f", rest @ ..] => (Action::Flag, rest),
+            ["c", rest @ ..] => (Action::Chord, rest),
+            rest => (Action::Reveal, rest),
+        };
         if parts.len() != 2 {
             return Err(GameError::InvalidInput);
         }
 
-        let x = parts[0].parse::<i32>()? - 1;
+        let x = parts[0].parse::<usize>()?;
+        let y = parts[1].parse::<usize>()?;
+        if x == 0 || x > self.config.row || y == 0 || y > self.config.col {
+            return Err(GameError::InvalidInput);
+        }
 
-        let y = parts[1].parse::<i32>()? - 1;
+        self.apply(action, x - 1, y - 1, status)
+    }
+
+    /// Apply a move at the zero-based position and record it.
+    pub fn apply(
+        &mut self,
+        action: Action,
+        x: usize,
+        y: usize,
+        status: &mut Status,
+    ) -> Result<(), GameError> {
+        match action {
+            Action::Reveal => self.clicks.left += 1,
+            Action::Flag => self.clicks.right += 1,
+            Action::Chord => self.clicks.chord += 1,
+        }
+        if let Err(e) = self.perform(action, x, y, status) {
+            self.clicks.wasted += 1;
+            return Err(e);
+        }
+        self.hint = None;
+        self.moves.push(Move {
+            action,
+            x,
+            y,
+            time: self.elapsed().as_millis() as u64,
+        });
+        self.check_finished(status);
+        Ok(())
+    }
+
+    /// Re-apply a recorded move, keeping its original timestamp.
+    pub fn replay(&mut self, mv: Move, status: &mut Status) -> Result<(), GameError> {
+        self.perform(mv.action, mv.x, mv.y, status)?;
+        self.moves.push(mv);
+        self.start = Instant::now();
+        self.offset = Duration::from_millis(mv.time);
+        self.check_finished(status);
+        Ok(())
+    }
+
+    /// Stop the clock once the game is won or lost.
+    fn check_finished(&mut self, status: &Status) {
+        if self.finished.is_none() && matches!(status, Status::Success | Status::Failed) {
+            self.finished = Some(self.elapsed());
+        }
+    }
+
+    fn perform(
+        &mut self,
+        action: Action,
+        x: usize,
+        y: usize,
+        status: &mut Status,
+    ) -> Result<(), GameError> {
+        match action {
+            Action::Reveal => self.reveal(x, y, status),
+            Action::Flag => self.toggle_flag(x, y),
+            Action::Chord => self.chord(x, y, status),
+        }
+    }
+
+    fn reveal(&mut self, x: usize, y: usize, status: &mut Status) -> Result<(), GameError> {
+        let cfg = self.config;
+        if self.flags[x][y] {
+            return Err(GameError::Flagged);
+        }
 
         if self.first {
+            self.safe_click = Some(self.moves.len());
             self.world = vec![vec![Item::Space; cfg.col]; cfg.row];
-            self.generate_mine_by_pos(y as usize, x as usize);
+            self.generate_mine_by_pos(y, x);
             self.generate_number();
         }
 
         self.first = false;
 
-        if x >= 0 && x < cfg.row as i32 && y >= 0 && y < cfg.col as i32 {
-            match self.world[x as usize][y as usize] {
-                Item::Mine => {
-                    self.draw_mine = true;
-                    *status = Status::Failed;
+        match self.world[x][y] {
+            Item::Mine => {
+                self.draw_mine = true;
+                *status = Status::Failed;
+            }
+            Item::Number(_) => {
+                if self.board[x][y] {
+                    return Err(GameError::AlreadyExploded);
                 }
-                Item::Number(_) => {
-                    if self.board[x as usize][y as usize] {
-                        return Err(GameError::AlreadyExploded);
-                    }
-                    self.board[x as usize][y as usize] = true;
-                    self.spread(x, y);
+                self.board[x][y] = true;
+                if let Item::Number(0) = self.world[x][y] {
+                    self.spread(x as i32, y as i32);
                 }
-                _ => (),
             }
-        } else {
-            return Err(GameError::InvalidInput);
+            _ => (),
         }
 
         if self.judge() {
@@ -222,29 +566,89 @@ impl Game {
         Ok(())
     }
 
+    /// Reveal the unflagged neighbours of a number that has as many flags around it.
+    fn chord(&mut self, x: usize, y: usize, status: &mut Status) -> Result<(), GameError> {
+        let Item::Number(num) = self.world[x][y] else {
+            return Err(GameError::InvalidChord);
+        };
+        let neighbours = self.neighbours(x, y);
+        let flagged = neighbours
+            .iter()
+            .filter(|&&(i, j)| self.flags[i][j] && !self.board[i][j])
+            .count();
+        if !self.board[x][y] || num == 0 || flagged != num as usize {
+            return Err(GameError::InvalidChord);
+        }
+        for (i, j) in neighbours {
+            if !self.board[i][j] && !self.flags[i][j] {
+                self.reveal(i, j, status)?;
+                if let Status::Failed = status {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Positions around a cell that lie on the board.
+    pub fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let Config { col, row, .. } = self.config;
+        let mut cells = Vec::with_capacity(8);
+        for i in x.saturating_sub(1)..=(x + 1).min(row - 1) {
+            for j in y.saturating_sub(1)..=(y + 1).min(col - 1) {
+                if (i, j) != (x, y) {
+                    cells.push((i, j));
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn revealed(&self, x: usize, y: usize) -> bool {
+        self.board[x][y]
+    }
+
+    /// The number shown on a revealed cell.
+    pub fn number(&self, x: usize, y: usize) -> Option<u8> {
+        match self.world[x][y] {
+            Item::Number(n) if self.board[x][y] => Some(n as u8),
+            _ => None,
+        }
+    }
+
+    pub fn flagged(&self, x: usize, y: usize) -> bool {
+        self.flags[x][y]
+    }
+
+    fn toggle_flag(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        if self.board[x][y] {
+            return Err(GameError::AlreadyExploded);
+        }
+        self.flags[x][y] = !self.flags[x][y];
+        Ok(())
+    }
+
     pub fn spread(&mut self, i: i32, j: i32) {
         let direction: [i32; 3] = [-1, 0, 1];
         let Config { col, row, .. } = self.config;
         for x in 0..3 {
             for y in 0..3 {
-                let px = i as i32 + direction[x];
-                let py = j as i32 + direction[y];
-
-                if px >= 0 && px < row as i32 && py >= 0 && py < col as i32 {
-                    match self.world[px as usize][py as usize] {
-                        Item::Number(num) => {
-                            if num == 0 {
-                                if !self.board[px as usize][py as usize] {
-                                    self.board[px as usize][py as usize] = true;
-                                    self.spread(px, py);
-                                }
-                            } else {
-                                if !self.board[px as usize][py as usize] {
-                                    self.board[px as usize][py as usize] = true;
-                                }
-                            }
+                let px = i + direction[x];
+                let py = j + direction[y];
+
+                if px >= 0
+                    && px < row as i32
+                    && py >= 0
+                    && py < col as i32
+                    && let Item::Number(num) = self.world[px as usize][py as usize]
+                {
+                    if num == 0 {
+                        if !self.board[px as usize][py as usize] {
+                            self.board[px as usize][py as usize] = true;
+                            self.spread(px, py);
                         }
-                        _ => (),
+                    } else if !self.board[px as usize][py as usize] {
+                        self.board[px as usize][py as usize] = true;
                     }
                 }
             }
@@ -252,16 +656,8 @@ impl Game {
     }
 
     pub fn judge(&self) -> bool {
-        let mut count = 0;
         let cfg = &self.config;
-        let board = &self.board;
-        for i in 0..cfg.row {
-            for j in 0..cfg.col {
-                if board[i][j] {
-                    count += 1;
-                }
-            }
-        }
+        let count = self.board.iter().flatten().filter(|&&b| b).count();
         if (cfg.col * cfg.row) - count == cfg.mine as usize {
             return true;
         }
@@ -280,6 +676,19 @@ impl Item {
     }
 }
 
+/// Green for safe through yellow to red for a certain mine.
+fn probability_color(c: char, p: Option<f64>) -> String {
+    let c = c.to_string();
+    match p {
+        Some(p) if p <= 0.0 => c.green().to_string(),
+        Some(p) if p < 0.2 => c.dark_green().to_string(),
+        Some(p) if p < 0.5 => c.yellow().to_string(),
+        Some(p) if p < 1.0 => c.dark_red().to_string(),
+        Some(_) => c.red().to_string(),
+        None => c.white().to_string(),
+    }
+}
+
 fn render_color(c: char) -> String {
     match c {
         '1' => '1'.to_string().blue().to_string(),
@@ -291,6 +700,7 @@ fn render_color(c: char) -> String {
         '7' => '7'.to_string().black().to_string(),
         '8' => '8'.to_string().grey().to_string(),
         '·' => '·'.to_string().white().to_string(),
+        'F' => 'F'.to_string().red().to_string(),
         'X' => 'X'.to_string().grey().to_string(),
         ' ' => ' '.to_string(),
         _ => "".to_string(),
@@ -17,3 +17,9 @@ impl Input {
         self.content = String::new();
     }
 }
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
+}
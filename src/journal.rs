@@ -0,0 +1,83 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::game::Move;
+use crate::metrics::Clicks;
+use crate::profile;
+use crate::save::SavedGame;
+use crate::storage;
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// One line of the journal: the state the game started from, then every
+/// move applied to it with the clicks counted so far.
+#[derive(Serialize, Deserialize)]
+enum Entry {
+    Start(SavedGame),
+    Move(Move, Clicks),
+}
+
+fn path(profile: &str) -> Result<PathBuf, StorageError> {
+    Ok(storage::data_dir()?.join(profile::file(profile, JOURNAL_FILE)))
+}
+
+/// Begin a new journal for the profile, replacing the previous one.
+pub fn start(profile: &str, state: &SavedGame) -> Result<(), StorageError> {
+    let path = path(profile)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", serde_json::to_string(&Entry::Start(state.clone()))?)?;
+    Ok(())
+}
+
+pub fn append(profile: &str, mv: &Move, clicks: Clicks) -> Result<(), StorageError> {
+    let mut file = OpenOptions::new().append(true).open(path(profile)?)?;
+    writeln!(file, "{}", serde_json::to_string(&Entry::Move(*mv, clicks))?)?;
+    Ok(())
+}
+
+pub fn clear(profile: &str) -> Result<(), StorageError> {
+    let path = path(profile)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Read back an interrupted game, if the journal holds any moves. The state
+/// carries the clicks counted up to the last move.
+///
+/// A line cut off by a crash ends the journal rather than failing it.
+pub fn recover(profile: &str) -> Result<Option<(SavedGame, Vec<Move>)>, StorageError> {
+    let path = path(profile)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let Some(Ok(first)) = lines.next() else {
+        return Ok(None);
+    };
+    let Entry::Start(mut state) = serde_json::from_str(&first)? else {
+        return Ok(None);
+    };
+    let mut moves = Vec::new();
+    for line in lines {
+        match serde_json::from_str(&line?) {
+            Ok(Entry::Move(mv, clicks)) => {
+                moves.push(mv);
+                state.clicks = clicks;
+            }
+            _ => break,
+        }
+    }
+    if moves.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((state, moves)))
+}
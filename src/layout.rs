@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::*;
+use crate::error::LayoutError;
+
+/// A mine layout in plain text, one line per row with `*` for mines and `.`
+/// for safe cells, optionally preceded by a `<width>x<height> <mines>` header:
+///
+/// ```text
+/// 4x3 2
+/// *...
+/// ..*.
+/// ....
+/// ```
+pub struct Layout {
+    pub config: Config,
+    pub mines: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    pub fn new(config: Config, mines: Vec<(usize, usize)>) -> Self {
+        Self { config, mines }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LayoutError> {
+        let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+
+        let header = match lines.peek() {
+            Some(line) if line.contains('x') => {
+                let header = parse_header(line)?;
+                lines.next();
+                Some(header)
+            }
+            _ => None,
+        };
+
+        let mut mines = Vec::new();
+        let mut col = None;
+        let mut row = 0;
+        for line in lines {
+            let mut width = 0;
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    '*' => mines.push((row, j)),
+                    '.' => {}
+                    c => return Err(LayoutError::InvalidChar(c, row + 1)),
+                }
+                width += 1;
+            }
+            if *col.get_or_insert(width) != width {
+                return Err(LayoutError::RaggedRows(row + 1));
+            }
+            row += 1;
+        }
+        let col = col.ok_or(LayoutError::Empty)?;
+
+        let config = Config::new(col, row, mines.len() as i32);
+        if let Some(header) = header
+            && (header.col, header.row, header.mine) != (config.col, config.row, config.mine)
+        {
+            return Err(LayoutError::HeaderMismatch);
+        }
+        Ok(Self { config, mines })
+    }
+
+    pub fn to_text(&self) -> String {
+        let Config { col, row, mine } = self.config;
+        let mut grid = vec![vec!['.'; col]; row];
+        for &(x, y) in &self.mines {
+            grid[x][y] = '*';
+        }
+        let mut text = format!("{}x{} {}\n", col, row, mine);
+        for line in grid {
+            text.extend(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LayoutError> {
+        fs::write(path, self.to_text())?;
+        Ok(())
+    }
+}
+
+/// Parse a `<width>x<height> <mines>` header line.
+pub fn parse_header(line: &str) -> Result<Config, LayoutError> {
+    let (size, mines) = line.split_once(' ').ok_or(LayoutError::InvalidHeader)?;
+    let (w, h) = size.split_once('x').ok_or(LayoutError::InvalidHeader)?;
+    let parse = |s: &str| s.trim().parse::<usize>().map_err(|_| LayoutError::InvalidHeader);
+    Ok(Config::new(parse(w)?, parse(h)?, parse(mines)? as i32))
+}
@@ -0,0 +1,27 @@
+pub mod analysis;
+pub mod app;
+pub mod bot;
+pub mod code;
+pub mod config;
+pub mod date;
+pub mod error;
+pub mod game;
+pub mod input;
+pub mod journal;
+pub mod layout;
+pub mod metrics;
+pub mod preset;
+pub mod profile;
+pub mod puzzle;
+pub mod raw;
+pub mod render;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod screen;
+pub mod share;
+pub mod solver;
+pub mod stats;
+pub mod storage;
+pub mod video;
+pub mod viewer;
@@ -1,28 +1,24 @@
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 
-mod game;
-use game::*;
-
-mod config;
-use config::*;
-
-mod screen;
-use screen::*;
-
-mod app;
-use app::*;
-
-mod render;
-use render::*;
-
-mod error;
-use error::*;
-
-mod input;
+use t_minesweeper::app::*;
+use t_minesweeper::config::*;
+use t_minesweeper::error::*;
+use t_minesweeper::journal;
+use t_minesweeper::puzzle;
+use t_minesweeper::render::*;
+use t_minesweeper::screen::*;
 
 use std::io::{Write, stdout};
+use std::path::Path;
+use std::time::Duration;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "solve") {
+        solve(&args[1..]);
+        return;
+    }
+
     let screen = Screen::new();
     screen.init();
     let mut app = App::new();
@@ -33,18 +29,24 @@ fn main() {
         render(&app).unwrap();
 
         stdout().flush().unwrap();
+        if app.ticking() && !event::poll(Duration::from_millis(50)).unwrap() {
+            app.tick();
+            continue;
+        }
         if let Event::Key(key) = event::read().unwrap() {
             if key.kind != KeyEventKind::Press {
                 continue;
             }
             match app.status {
                 Status::Game => match key.code {
-                    KeyCode::Char('q') => {
-                        app.status = Status::Welcome;
+                    KeyCode::Char('q') if app.input.content.is_empty() => {
+                        if let Err(e) = app.quit_game() {
+                            app.input.error_msg = format!("{}", e).into();
+                            app.status = Status::Welcome;
+                        }
                     }
                     KeyCode::Enter => {
-                        let game = app.game.as_mut().ok_or(RenderError::NoGame).unwrap();
-                        let res = game.handle_enter(&app.input.content, &mut app.status);
+                        let res = handle_game_command(&mut app);
                         if let Err(e) = res {
                             app.input.error_msg = format!("{}", e).into();
                         } else {
@@ -52,7 +54,22 @@ fn main() {
                         }
                         app.input.clear();
                     }
-                    KeyCode::Char(c) if c.is_numeric() || c == ' ' => {
+                    KeyCode::Char(c) => {
+                        app.input.content.push(c);
+                    }
+                    _ => {}
+                },
+                Status::Replay => match key.code {
+                    KeyCode::Enter => {
+                        let res = handle_replay_command(&mut app);
+                        if let Err(e) = res {
+                            app.input.error_msg = format!("{}", e).into();
+                        } else {
+                            app.input.error_msg = "".into();
+                        }
+                        app.input.clear();
+                    }
+                    KeyCode::Char(c) => {
                         app.input.content.push(c);
                     }
                     _ => {}
@@ -83,7 +100,115 @@ fn main() {
     }
 }
 
+/// `solve <file>`: print what follows from a partially revealed board.
+fn solve(args: &[String]) {
+    let [path] = args else {
+        eprintln!("usage: t-minesweeper solve <file>");
+        std::process::exit(2);
+    };
+    match puzzle::load(Path::new(path)) {
+        Ok(pos) => print!("{}", puzzle::report(&pos)),
+        Err(e) => {
+            eprintln!("{}", AppError::from(e));
+            std::process::exit(1);
+        }
+    }
+}
+
+fn handle_game_command(app: &mut App) -> Result<(), AppError> {
+    match app.input.content.trim() {
+        "save" => app.save_game(),
+        "prob" => app.toggle_overlay(None),
+        "hint" => app.hint(),
+        "bot" => app.toggle_bot(None),
+        s if s.starts_with("bot ") => {
+            let speed = s["bot ".len()..].trim();
+            let speed = speed
+                .parse::<f64>()
+                .ok()
+                .filter(|&v| v > 0.0 && v.is_finite())
+                .ok_or(AppError::UnknownCmd(s.into()))?;
+            app.toggle_bot(Some(speed))
+        }
+        s if s.starts_with("prob ") => {
+            let focus = parse_position(app, &s["prob ".len()..])?;
+            app.toggle_overlay(Some(focus))
+        }
+        s if s.starts_with("export ") => {
+            let path = s["export ".len()..].trim().to_string();
+            app.export_board(Path::new(&path))
+        }
+        _ => {
+            let input = app.input.content.clone();
+            app.play(&input)
+        }
+    }
+}
+
+fn handle_replay_command(app: &mut App) -> Result<(), AppError> {
+    let viewer = app.viewer.as_mut().ok_or(RenderError::NoGame).unwrap();
+    viewer.tick();
+    match app.input.content.trim() {
+        "q" => app.status = Status::Welcome,
+        "" | "p" => viewer.toggle(),
+        "n" => viewer.step_forward(),
+        "b" => viewer.step_back(),
+        "+" => viewer.faster(),
+        "-" => viewer.slower(),
+        "s" => viewer.seek(0),
+        "e" => viewer.seek(u64::MAX),
+        s if s.starts_with("export-raw ") => {
+            let path = s["export-raw ".len()..].trim().to_string();
+            app.export_raw(Path::new(&path))?;
+        }
+        s => {
+            let secs = s.parse::<f64>().map_err(|_| AppError::UnknownCmd(s.into()))?;
+            if secs < 0.0 {
+                return Err(AppError::UnknownCmd(s.into()));
+            }
+            viewer.seek((secs * 1000.0) as u64);
+        }
+    }
+    Ok(())
+}
+
+/// Actions offered on the end screens, returns whether the input was one of them.
+fn handle_end_command(app: &mut App) -> Result<bool, AppError> {
+    match app.input.content.trim() {
+        "r" => app.retry()?,
+        "g" => app.new_game()?,
+        "u" => app.continue_game()?,
+        "share" => app.share(false)?,
+        "share ascii" => app.share(true)?,
+        "review" => app.review()?,
+        s if s.starts_with("s ") => {
+            let path = s["s ".len()..].trim().to_string();
+            app.save_replay(Path::new(&path))?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn handle_restore(app: &mut App) -> Result<(), AppError> {
+    match app.input.content.trim() {
+        "y" => app.restore_game()?,
+        "n" => {
+            journal::clear(&app.profile)?;
+            app.status = Status::Welcome;
+        }
+        c => return Err(AppError::UnknownCmd(c.into())),
+    }
+    Ok(())
+}
+
 fn handle_command(app: &mut App) -> Result<(), AppError> {
+    if let Status::Restore = app.status {
+        return handle_restore(app);
+    }
+    if matches!(app.status, Status::Success | Status::Failed) && handle_end_command(app)? {
+        return Ok(());
+    }
     match app.input.content.trim() {
         "q" => match app.status {
             Status::Welcome => {
@@ -93,42 +218,117 @@ fn handle_command(app: &mut App) -> Result<(), AppError> {
                 app.status = Status::Welcome;
             }
         },
-        "e" => {
-            let mut g = Game::new(Config::easy());
-            g.generate();
-            app.game = Some(g);
-            app.status = Status::Game;
+        "e" => app.start_game(Config::easy(), Mode::Easy)?,
+        "n" => app.start_game(Config::normal(), Mode::Normal)?,
+        "h" => app.start_game(Config::hard(), Mode::Hard)?,
+        "d:e" => app.start_daily("easy")?,
+        "d:n" => app.start_daily("normal")?,
+        "d:h" => app.start_daily("hard")?,
+        "load" => app.load_game()?,
+        "s" => app.status = Status::Stats,
+        "l" => app.status = Status::Scores,
+        s if s.starts_with("profile ") => {
+            let name = s["profile ".len()..].trim().to_string();
+            app.switch_profile(&name)?;
         }
-        "n" => {
-            let mut g = Game::new(Config::normal());
-            g.generate();
-            app.game = Some(g);
-            app.status = Status::Game;
+        s if s.starts_with("name ") => {
+            let name = s["name ".len()..].trim().to_string();
+            app.settings.player = (!name.is_empty()).then_some(name);
+            app.settings.save(&app.profile)?;
+        }
+        s if s.starts_with("hint-limit ") => {
+            app.settings.hint_limit = match s["hint-limit ".len()..].trim() {
+                "off" => None,
+                n => Some(n.parse::<u32>()?),
+            };
+            app.settings.save(&app.profile)?;
+        }
+        "reset-stats" => {
+            app.stats.reset();
+            app.stats.save(&app.profile)?;
+        }
+        "replay" => app.open_replay(None)?,
+        s if s.to_uppercase().starts_with("TM-") => {
+            let code = s.to_string();
+            app.play_code(&code)?;
+        }
+        s if s.starts_with("import ") => {
+            let path = s["import ".len()..].trim().to_string();
+            app.import_board(Path::new(&path))?;
         }
-        "h" => {
-            let mut g = Game::new(Config::hard());
-            g.generate();
-            app.game = Some(g);
-            app.status = Status::Game;
+        s if s.starts_with("export ") => {
+            let path = s["export ".len()..].trim().to_string();
+            app.export_board(Path::new(&path))?;
+        }
+        s if s.starts_with("export-raw ") => {
+            let path = s["export-raw ".len()..].trim().to_string();
+            app.export_raw(Path::new(&path))?;
+        }
+        s if s.starts_with("replay ") => {
+            let path = s["replay ".len()..].trim().to_string();
+            app.open_replay(Some(&path))?;
         }
         s if s.starts_with("c:") => {
-            let parts: Vec<&str> = s[2..].trim().split_whitespace().collect();
-
-            if parts.len() != 3 {
+            let parts: Vec<&str> = s[2..].split_whitespace().collect();
+            let cfg = parse_config(&parts)?;
+            app.start_game(cfg, Mode::Custom)?;
+        }
+        s if s.starts_with("p:") => {
+            let name = s[2..].trim();
+            let cfg = app
+                .presets
+                .get(name)
+                .ok_or(AppError::UnknownPreset(name.into()))?;
+            app.start_game(cfg, Mode::Preset(name.to_string()))?;
+        }
+        s if s.starts_with("save-preset ") => {
+            let parts: Vec<&str> = s.split_whitespace().skip(1).collect();
+            if parts.len() != 4 {
                 return Err(AppError::InvalidCustom);
             }
-            let w = parts[0].parse::<usize>()?;
-            let h = parts[1].parse::<usize>()?;
-            let mines = parts[2].parse::<i32>()?;
-            let mut g = Game::new(Config::new(w, h, mines));
-            g.generate();
-            app.game = Some(g);
-            app.status = Status::Game;
-        }
-        c if c != "" => {
+            let cfg = parse_config(&parts[1..])?;
+            app.presets.insert(parts[0], cfg);
+            app.presets.save()?;
+        }
+        s if s.starts_with("delete-preset ") => {
+            let name = s["delete-preset ".len()..].trim();
+            app.presets
+                .remove(name)
+                .ok_or(AppError::UnknownPreset(name.into()))?;
+            app.presets.save()?;
+        }
+        c if !c.is_empty() => {
             return Err(AppError::UnknownCmd(c.into()));
         }
         _ => {}
     }
     Ok(())
 }
+
+/// Parse `<X> <Y>` into a zero-based position on the current board.
+fn parse_position(app: &App, s: &str) -> Result<(usize, usize), AppError> {
+    let game = app.game.as_ref().ok_or(AppError::NoGame)?;
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let [x, y] = parts.as_slice() else {
+        return Err(GameError::InvalidInput.into());
+    };
+    let (x, y) = (x.parse::<usize>()?, y.parse::<usize>()?);
+    if x == 0 || x > game.config.row || y == 0 || y > game.config.col {
+        return Err(GameError::InvalidInput.into());
+    }
+    Ok((x - 1, y - 1))
+}
+
+/// Parse `<width> <height> <mines>` into a playable config.
+fn parse_config(parts: &[&str]) -> Result<Config, AppError> {
+    if parts.len() != 3 {
+        return Err(AppError::InvalidCustom);
+    }
+    let w = parts[0].parse::<usize>()?;
+    let h = parts[1].parse::<usize>()?;
+    let mines = parts[2].parse::<i32>()?;
+    if w == 0 || h == 0 || mines < 0 || mines as usize >= w * h {
+        return Err(AppError::InvalidCustom);
+    }
+    Ok(Config::new(w, h, mines))
+}
@@ -0,0 +1,324 @@
+use crate::config::*;
+
+/// Difficulty measures of a mine layout.
+#[derive(Clone, Copy, Default)]
+pub struct BoardMetrics {
+    /// Bechtel's Board Benchmark Value: the fewest left clicks that clear the board.
+    pub bbbv: usize,
+    /// Connected areas of empty squares, each cleared by a single click.
+    pub openings: usize,
+    /// Connected groups of numbers that don't border an opening.
+    pub islands: usize,
+    /// Clicks needed by greedy chording, flags included.
+    pub zini: usize,
+}
+
+/// Clicks made during a game.
+#[derive(Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct Clicks {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+    /// Clicks that didn't change the board.
+    pub wasted: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+
+    pub fn effective(&self) -> u32 {
+        self.total() - self.wasted
+    }
+
+    /// Share of clicks that changed the board.
+    pub fn correctness(&self) -> f64 {
+        ratio(self.effective() as f64, self.total() as f64)
+    }
+}
+
+/// 3BV per click, the index of efficiency.
+pub fn ioe(bbbv: usize, clicks: &Clicks) -> f64 {
+    ratio(bbbv as f64, clicks.total() as f64)
+}
+
+pub fn bbbv_per_second(bbbv: usize, secs: f64) -> f64 {
+    ratio(bbbv as f64, secs)
+}
+
+fn ratio(a: f64, b: f64) -> f64 {
+    if b > 0.0 { a / b } else { 0.0 }
+}
+
+struct Grid {
+    col: usize,
+    row: usize,
+    /// Number of adjacent mines, `-1` for a mine.
+    cells: Vec<Vec<i8>>,
+}
+
+impl Grid {
+    fn new(cfg: &Config, mines: &[(usize, usize)]) -> Self {
+        let mut cells = vec![vec![0; cfg.col]; cfg.row];
+        for &(x, y) in mines {
+            cells[x][y] = -1;
+        }
+        let mut grid = Self {
+            col: cfg.col,
+            row: cfg.row,
+            cells,
+        };
+        for &(x, y) in mines {
+            for (i, j) in grid.neighbours(x, y) {
+                if grid.cells[i][j] >= 0 {
+                    grid.cells[i][j] += 1;
+                }
+            }
+        }
+        grid
+    }
+
+    fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(8);
+        for i in x.saturating_sub(1)..=(x + 1).min(self.row - 1) {
+            for j in y.saturating_sub(1)..=(y + 1).min(self.col - 1) {
+                if (i, j) != (x, y) {
+                    cells.push((i, j));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Label every opening, numbers on its border included, returning the
+    /// labels and the number of openings.
+    fn openings(&self) -> (Vec<Vec<Option<usize>>>, usize) {
+        let mut labels = vec![vec![None; self.col]; self.row];
+        let mut count = 0;
+        for x in 0..self.row {
+            for y in 0..self.col {
+                if self.cells[x][y] != 0 || labels[x][y].is_some() {
+                    continue;
+                }
+                let mut stack = vec![(x, y)];
+                labels[x][y] = Some(count);
+                while let Some((i, j)) = stack.pop() {
+                    for (a, b) in self.neighbours(i, j) {
+                        if labels[a][b].is_none() {
+                            labels[a][b] = Some(count);
+                            if self.cells[a][b] == 0 {
+                                stack.push((a, b));
+                            }
+                        }
+                    }
+                }
+                count += 1;
+            }
+        }
+        (labels, count)
+    }
+}
+
+pub fn analyse(cfg: &Config, mines: &[(usize, usize)]) -> BoardMetrics {
+    if cfg.col == 0 || cfg.row == 0 {
+        return BoardMetrics::default();
+    }
+    let grid = Grid::new(cfg, mines);
+    let (labels, openings) = grid.openings();
+
+    let isolated = |x: usize, y: usize| grid.cells[x][y] > 0 && labels[x][y].is_none();
+    let mut bbbv = openings;
+    let mut islands = 0;
+    let mut seen = vec![vec![false; grid.col]; grid.row];
+    for x in 0..grid.row {
+        for y in 0..grid.col {
+            if !isolated(x, y) {
+                continue;
+            }
+            bbbv += 1;
+            if seen[x][y] {
+                continue;
+            }
+            islands += 1;
+            let mut stack = vec![(x, y)];
+            seen[x][y] = true;
+            while let Some((i, j)) = stack.pop() {
+                for (a, b) in grid.neighbours(i, j) {
+                    if isolated(a, b) && !seen[a][b] {
+                        seen[a][b] = true;
+                        stack.push((a, b));
+                    }
+                }
+            }
+        }
+    }
+
+    BoardMetrics {
+        bbbv,
+        openings,
+        islands,
+        zini: zini(&grid, &labels, openings),
+    }
+}
+
+/// How much of the 3BV has been cleared on a partly revealed board.
+pub fn cleared(cfg: &Config, mines: &[(usize, usize)], revealed: &[Vec<bool>]) -> usize {
+    if cfg.col == 0 || cfg.row == 0 {
+        return 0;
+    }
+    let grid = Grid::new(cfg, mines);
+    let (labels, openings) = grid.openings();
+    let mut done = vec![false; openings];
+    let mut count = 0;
+    for x in 0..grid.row {
+        for y in 0..grid.col {
+            if !revealed[x][y] {
+                continue;
+            }
+            match (grid.cells[x][y], labels[x][y]) {
+                (0, Some(id)) if !done[id] => {
+                    done[id] = true;
+                    count += 1;
+                }
+                (n, None) if n > 0 => count += 1,
+                _ => {}
+            }
+        }
+    }
+    count
+}
+
+/// Greedy ZiNi: keep chording the number that clears the most 3BV for the
+/// fewest clicks, then click whatever is left.
+fn zini(grid: &Grid, labels: &[Vec<Option<usize>>], openings: usize) -> usize {
+    let mut opened = vec![vec![false; grid.col]; grid.row];
+    let mut flagged = vec![vec![false; grid.col]; grid.row];
+    let mut opening_done = vec![false; openings];
+    let mut clicks = 0;
+
+    // The 3BV a square still stands for: its opening, or itself if isolated.
+    let unit = |x: usize, y: usize, opened: &[Vec<bool>], done: &[bool]| -> Option<usize> {
+        match (grid.cells[x][y], labels[x][y]) {
+            (0, Some(id)) if !done[id] => Some(openings + id),
+            (n, None) if n > 0 && !opened[x][y] => Some(x * grid.col + y + 2 * openings),
+            _ => None,
+        }
+    };
+
+    let open = |x: usize, y: usize, opened: &mut Vec<Vec<bool>>, done: &mut Vec<bool>| {
+        if grid.cells[x][y] == 0
+            && let Some(id) = labels[x][y]
+        {
+            done[id] = true;
+            for i in 0..grid.row {
+                for j in 0..grid.col {
+                    if labels[i][j] == Some(id) {
+                        opened[i][j] = true;
+                    }
+                }
+            }
+        } else {
+            opened[x][y] = true;
+        }
+    };
+
+    loop {
+        let mut best: Option<(i64, usize, usize)> = None;
+        for x in 0..grid.row {
+            for y in 0..grid.col {
+                if grid.cells[x][y] <= 0 {
+                    continue;
+                }
+                let around = grid.neighbours(x, y);
+                let flags = around
+                    .iter()
+                    .filter(|&&(i, j)| grid.cells[i][j] < 0 && !flagged[i][j])
+                    .count();
+                let mut units: Vec<usize> = around
+                    .iter()
+                    .filter(|&&(i, j)| grid.cells[i][j] >= 0)
+                    .filter_map(|&(i, j)| unit(i, j, &opened, &opening_done))
+                    .collect();
+                units.sort();
+                units.dedup();
+                let cost = flags + 1 + usize::from(!opened[x][y]);
+                let gain = units.len() + usize::from(unit(x, y, &opened, &opening_done).is_some());
+                let premium = gain as i64 - cost as i64;
+                if premium > 0 && best.is_none_or(|(p, _, _)| premium > p) {
+                    best = Some((premium, x, y));
+                }
+            }
+        }
+        let Some((_, x, y)) = best else {
+            break;
+        };
+
+        let around = grid.neighbours(x, y);
+        if !opened[x][y] {
+            clicks += 1;
+            open(x, y, &mut opened, &mut opening_done);
+        }
+        for &(i, j) in &around {
+            if grid.cells[i][j] < 0 && !flagged[i][j] {
+                flagged[i][j] = true;
+                clicks += 1;
+            }
+        }
+        clicks += 1;
+        for &(i, j) in &around {
+            if grid.cells[i][j] >= 0 && !opened[i][j] {
+                open(i, j, &mut opened, &mut opening_done);
+            }
+        }
+    }
+
+    let mut rest: Vec<usize> = (0..grid.row)
+        .flat_map(|x| (0..grid.col).map(move |y| (x, y)))
+        .filter_map(|(x, y)| unit(x, y, &opened, &opening_done))
+        .collect();
+    rest.sort();
+    rest.dedup();
+    clicks + rest.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(col: usize, row: usize, mines: &[(usize, usize)]) -> (usize, usize, usize, usize) {
+        let m = analyse(&Config::new(col, row, mines.len() as i32), mines);
+        (m.bbbv, m.openings, m.islands, m.zini)
+    }
+
+    #[test]
+    fn one_opening() {
+        // A mine in the corner, every number borders the opening.
+        assert_eq!(metrics(3, 3, &[(0, 0)]), (1, 1, 0, 1));
+    }
+
+    #[test]
+    fn islands() {
+        // 1 * 1: two numbers that don't touch.
+        assert_eq!(metrics(3, 1, &[(0, 1)]), (2, 0, 2, 2));
+    }
+
+    #[test]
+    fn chording_beats_clicking() {
+        // A ring of eight 1s around a mine: clicking each costs 8. Opening the
+        // top 1, flagging and chording it, chording the left 1 and clicking
+        // the last corner costs 5.
+        assert_eq!(metrics(3, 3, &[(1, 1)]), (8, 0, 1, 5));
+    }
+
+    #[test]
+    fn cleared_after_loss() {
+        let cfg = Config::new(3, 3, 1);
+        let mut revealed = vec![vec![false; 3]; 3];
+        revealed[0][1] = true;
+        revealed[1][0] = true;
+        revealed[1][1] = true;
+        assert_eq!(cleared(&cfg, &[(1, 1)], &revealed), 2);
+        assert_eq!(cleared(&cfg, &[(1, 1)], &vec![vec![false; 3]; 3]), 0);
+    }
+}
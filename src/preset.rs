@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::*;
+use crate::error::StorageError;
+use crate::storage;
+
+const PRESETS_FILE: &str = "presets.json";
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Presets {
+    presets: BTreeMap<String, Config>,
+}
+
+impl Presets {
+    pub fn load() -> Result<Self, StorageError> {
+        storage::load(PRESETS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), StorageError> {
+        storage::save(PRESETS_FILE, self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Config> {
+        self.presets.get(name).copied()
+    }
+
+    pub fn insert(&mut self, name: &str, cfg: Config) {
+        self.presets.insert(name.to_string(), cfg);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Config> {
+        self.presets.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Config)> {
+        self.presets.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, StorageError};
+use crate::storage;
+
+/// The profile used before any other is created, it keeps its files at the
+/// top of the data directory.
+pub const DEFAULT: &str = "default";
+
+const PROFILES_DIR: &str = "profiles";
+const CURRENT_FILE: &str = "profile.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Per-profile preferences.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Name shown in high scores and exported replays.
+    pub player: Option<String>,
+    /// Hints allowed per game, unlimited if not set.
+    pub hint_limit: Option<u32>,
+}
+
+impl Settings {
+    pub fn load(profile: &str) -> Result<Self, StorageError> {
+        storage::load(&file(profile, SETTINGS_FILE))
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), StorageError> {
+        storage::save(&file(profile, SETTINGS_FILE), self)
+    }
+}
+
+/// Path of a profile's file, relative to the data directory.
+pub fn file(profile: &str, name: &str) -> String {
+    if profile == DEFAULT {
+        name.to_string()
+    } else {
+        format!("{}/{}/{}", PROFILES_DIR, profile, name)
+    }
+}
+
+pub fn dir(profile: &str) -> Result<PathBuf, StorageError> {
+    Ok(storage::data_dir()?.join(file(profile, "")))
+}
+
+/// The profile selected last time.
+pub fn current() -> String {
+    storage::load::<Option<String>>(CURRENT_FILE)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| DEFAULT.to_string())
+}
+
+pub fn set_current(profile: &str) -> Result<(), StorageError> {
+    storage::save(CURRENT_FILE, &profile)
+}
+
+/// All profiles, the default one first.
+pub fn list() -> Result<Vec<String>, StorageError> {
+    let mut profiles = vec![DEFAULT.to_string()];
+    let dir = storage::data_dir()?.join(PROFILES_DIR);
+    if dir.exists() {
+        let mut names = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        names.sort();
+        profiles.extend(names);
+    }
+    Ok(profiles)
+}
+
+pub fn validate(name: &str) -> Result<(), AppError> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(AppError::InvalidName(name.into()));
+    }
+    Ok(())
+}
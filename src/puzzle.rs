@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::LayoutError;
+use crate::layout::parse_header;
+use crate::solver::*;
+
+/// A position to solve in plain text, one line per row: `0` to `8` for
+/// revealed numbers (`_` also for an empty square), `.`, `?` or `#` for hidden
+/// cells and `F` or `*` for flags. Spaces are ignored. A `<width>x<height>
+/// <mines>` header, as in board files, gives the total number of mines:
+///
+/// ```text
+/// 4x3 2
+/// 01F.
+/// 01..
+/// 001.
+/// ```
+pub fn parse(text: &str) -> Result<Position, LayoutError> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+
+    let header = match lines.peek() {
+        Some(line) if line.contains('x') => {
+            let header = parse_header(line)?;
+            lines.next();
+            Some(header)
+        }
+        _ => None,
+    };
+
+    let mut cells = Vec::new();
+    for (row, line) in lines.enumerate() {
+        let mut cols = Vec::new();
+        for c in line.chars().filter(|c| !c.is_whitespace()) {
+            cols.push(match c {
+                '0'..='8' => Cell::Number(c as u8 - b'0'),
+                '_' => Cell::Number(0),
+                '.' | '?' | '#' => Cell::Hidden,
+                'F' | '*' => Cell::Flagged,
+                c => return Err(LayoutError::InvalidChar(c, row + 1)),
+            });
+        }
+        if cells.first().is_some_and(|first: &Vec<Cell>| first.len() != cols.len()) {
+            return Err(LayoutError::RaggedRows(row + 1));
+        }
+        cells.push(cols);
+    }
+    if cells.is_empty() {
+        return Err(LayoutError::Empty);
+    }
+
+    if let Some(header) = header
+        && (header.col, header.row) != (cells[0].len(), cells.len())
+    {
+        return Err(LayoutError::HeaderMismatch);
+    }
+    let mut pos = Position::new(cells, None);
+    let Some(header) = header else {
+        return Ok(pos);
+    };
+    // Every flag is a mine and every mine is flagged or hidden.
+    let (flags, hidden) = (pos.flags(), pos.hidden().len());
+    if header.mine < 0 || !(flags..=flags + hidden).contains(&(header.mine as usize)) {
+        return Err(LayoutError::MineCount(header.mine));
+    }
+    pos.mines = Some(header.mine as usize);
+    Ok(pos)
+}
+
+pub fn load(path: &Path) -> Result<Position, LayoutError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// What can be said about a position: the cells proven safe or mined with
+/// the reasoning, then the mine probability of every hidden cell. A position
+/// no arrangement of mines fits is only called out as such.
+pub fn report(pos: &Position) -> String {
+    let deductions = solve(pos);
+    let probs = probabilities(pos);
+
+    let mut s = format!("{} x {} board", pos.cols(), pos.rows());
+    match pos.mines {
+        Some(mines) => s += &format!(", {} mines, {} flagged\n", mines, pos.flags()),
+        None => s += &format!(", mine count unknown, {} flagged\n", pos.flags()),
+    }
+    if !probs.consistent {
+        s += "\nNo arrangement of mines fits these numbers, the position is inconsistent.\n";
+        return s;
+    }
+    s += "Positions are <X>,<Y>: row, then column, from 1.\n\n";
+
+    // Cells only counting every arrangement shows to be empty follow the ones
+    // a rule proves.
+    let mut safe: Vec<((usize, usize), String)> = Vec::new();
+    let mut mines = Vec::new();
+    for d in &deductions {
+        let found = ((d.x, d.y), d.explain(pos));
+        match d.verdict {
+            Verdict::Safe => safe.push(found),
+            Verdict::Mine => mines.push(found),
+        }
+    }
+    for cell in probs.safe() {
+        if !safe.iter().any(|(c, _)| *c == cell) {
+            safe.push((cell, explain_exact(cell)));
+        }
+    }
+    for (found, title) in [(&safe, "Safe cells"), (&mines, "Mines")] {
+        if found.is_empty() {
+            s += &format!("{}: none\n", title);
+            continue;
+        }
+        s += &format!("{}:\n", title);
+        for ((x, y), reason) in found {
+            s += &format!("  {},{}  {}\n", x + 1, y + 1, reason);
+        }
+    }
+
+    s += &format!(
+        "\nMine probabilities ({}):\n     ",
+        if probs.exact { "exact" } else { "estimated" }
+    );
+    for y in 0..pos.cols() {
+        s += &format!("{:>5}", y + 1);
+    }
+    s.push('\n');
+    for x in 0..pos.rows() {
+        s += &format!("{:>5}", x + 1);
+        for y in 0..pos.cols() {
+            let cell = match (pos.get(x, y), probs.get(x, y)) {
+                (Cell::Number(n), _) => n.to_string(),
+                (Cell::Flagged, _) => "F".to_string(),
+                (Cell::Hidden, Some(p)) => format!("{:.0}%", p * 100.0),
+                (Cell::Hidden, None) => "?".to_string(),
+            };
+            s += &format!("{:>5}", cell);
+        }
+        s.push('\n');
+    }
+
+    if safe.is_empty()
+        && let Some((x, y, p)) = probs.safest()
+    {
+        s += &format!(
+            "\nNo cell is provably safe, the safest guess is {},{} at {:.1}%\n",
+            x + 1,
+            y + 1,
+            p * 100.0
+        );
+    }
+    s
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::app::Status;
+use crate::config::*;
+use crate::error::ReplayError;
+use crate::game::*;
+use crate::replay::*;
+
+/// Size of a square in pixels, used for the pixel positions of events.
+pub(crate) const SQUARE: usize = 16;
+
+/// Parse a replay in the RAW Minesweeper replay format (RawVF):
+///
+/// ```text
+/// RawVF_Version: Rev5
+/// Width: 8
+/// Height: 8
+/// Mines: 10
+/// Board:
+/// *0000000
+/// ...
+/// Events:
+/// 0.00 start
+/// 0.12 lc 3 4 (40 56)
+/// 0.20 lr 3 4 (40 56)
+/// ```
+///
+/// Mouse events are turned into moves by [`moves`].
+pub fn parse(text: &str) -> Result<Replay, ReplayError> {
+    let mut lines = text.lines().map(str::trim).enumerate();
+
+    let mut header = BTreeMap::new();
+    for (_, line) in lines.by_ref() {
+        if line == "Board:" {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            header.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    let field = |key: &'static str| -> Result<usize, ReplayError> {
+        header
+            .get(key)
+            .ok_or(ReplayError::MissingField(key))?
+            .parse()
+            .map_err(|_| ReplayError::MissingField(key))
+    };
+    let col = field("Width")?;
+    let row = field("Height")?;
+
+    let mut mines = Vec::new();
+    for x in 0..row {
+        let (n, line) = lines.next().ok_or(ReplayError::InvalidBoard)?;
+        if line.chars().count() != col {
+            return Err(ReplayError::InvalidLine(n + 1));
+        }
+        for (y, c) in line.chars().enumerate() {
+            if c == '*' {
+                mines.push((x, y));
+            }
+        }
+    }
+    if field("Mines").is_ok_and(|m| m != mines.len()) {
+        return Err(ReplayError::InvalidBoard);
+    }
+    let config = Config::new(col, row, mines.len() as i32);
+
+    let mut events = Vec::new();
+    for (n, line) in lines.skip_while(|(_, l)| *l != "Events:").skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [time, kind, y, x, ..] = parts.as_slice() else {
+            continue;
+        };
+        let time = time.parse::<f64>().map_err(|_| ReplayError::InvalidLine(n + 1))?;
+        let (Ok(x), Ok(y)) = (x.parse::<usize>(), y.parse::<usize>()) else {
+            continue;
+        };
+        events.push(Event {
+            time: (time.max(0.0) * 1000.0).round() as u64,
+            kind,
+            x,
+            y,
+        });
+    }
+
+    Ok(Replay {
+        config,
+        mode: Mode::Imported,
+        seed: 0,
+        moves: moves(config, &mines, events),
+        mines,
+    })
+}
+
+/// A mouse event in RawVF terms, which the binary formats are translated to.
+pub(crate) struct Event<'a> {
+    /// Milliseconds since the start of the game.
+    pub time: u64,
+    /// One of `lc`, `lr`, `rc`, `rr`, `mc` or `mr`; anything else is ignored.
+    pub kind: &'a str,
+    /// One-based row and column of the square under the cursor.
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Play mouse events on the board and keep the moves the game would have
+/// applied: a left release on a hidden square reveals it, a right press flags
+/// it and releasing one button while the other is held (or the middle button)
+/// chords. Events off the board are skipped.
+pub(crate) fn moves<'a>(
+    config: Config,
+    mines: &[(usize, usize)],
+    events: impl IntoIterator<Item = Event<'a>>,
+) -> Vec<Move> {
+    let mut game = Game::with_mines(config, Mode::Imported, 0, mines);
+    let mut status = Status::Replay;
+    let mut moves = Vec::new();
+    let (mut left, mut right) = (false, false);
+    for Event { time, kind, x, y } in events {
+        if x == 0 || x > config.row || y == 0 || y > config.col {
+            continue;
+        }
+        let (x, y) = (x - 1, y - 1);
+
+        let action = match kind {
+            "lc" => {
+                left = true;
+                None
+            }
+            "lr" => {
+                left = false;
+                Some(if right { Action::Chord } else { Action::Reveal })
+            }
+            "rc" => {
+                right = true;
+                (!left).then_some(Action::Flag)
+            }
+            "rr" => {
+                right = false;
+                left.then_some(Action::Chord)
+            }
+            "mr" => Some(Action::Chord),
+            _ => None,
+        };
+        let Some(action) = action else {
+            continue;
+        };
+        let action = match action {
+            Action::Reveal if game.revealed(x, y) => continue,
+            Action::Flag if game.revealed(x, y) => continue,
+            Action::Chord if !game.revealed(x, y) => continue,
+            action => action,
+        };
+
+        let mv = Move { action, x, y, time };
+        if game.replay(mv, &mut status).is_ok() {
+            moves.push(mv);
+        }
+        if matches!(status, Status::Success | Status::Failed) {
+            break;
+        }
+    }
+    moves
+}
+
+/// Write a replay in the RAW Minesweeper replay format, each move becoming the
+/// mouse presses and releases that [`parse`] turns back into it.
+pub fn export(replay: &Replay, player: &str) -> String {
+    let Config { col, row, mine } = replay.config;
+    let mut text = String::new();
+    let _ = writeln!(text, "RawVF_Version: Rev5");
+    let _ = writeln!(text, "Program: t-minesweeper");
+    let _ = writeln!(text, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(text, "Player: {}", player);
+    let _ = writeln!(text, "Level: {}", level(&replay.config));
+    let _ = writeln!(text, "Width: {}", col);
+    let _ = writeln!(text, "Height: {}", row);
+    let _ = writeln!(text, "Mines: {}", mine);
+    let _ = writeln!(text, "Marks: Off");
+    let _ = writeln!(text, "Mode: Classic");
+    let _ = writeln!(text, "Time: {:.3}", replay.duration() as f64 / 1000.0);
+
+    let mut grid = vec![vec!['0'; col]; row];
+    for &(x, y) in &replay.mines {
+        grid[x][y] = '*';
+    }
+    let _ = writeln!(text, "Board:");
+    for line in grid {
+        text.extend(line);
+        text.push('\n');
+    }
+
+    let _ = writeln!(text, "Events:");
+    let _ = writeln!(text, "0.000 start");
+    for mv in &replay.moves {
+        let kinds: &[&str] = match mv.action {
+            Action::Reveal => &["lc", "lr"],
+            Action::Flag => &["rc", "rr"],
+            Action::Chord => &["lc", "rc", "lr", "rr"],
+        };
+        for kind in kinds {
+            let _ = writeln!(
+                text,
+                "{:.3} {} {} {} ({} {})",
+                mv.time as f64 / 1000.0,
+                kind,
+                mv.y + 1,
+                mv.x + 1,
+                mv.y * SQUARE + SQUARE / 2,
+                mv.x * SQUARE + SQUARE / 2,
+            );
+        }
+    }
+    text
+}
+
+fn level(cfg: &Config) -> &'static str {
+    match (cfg.col, cfg.row, cfg.mine) {
+        (8, 8, 10) => "Beginner",
+        (16, 16, 40) => "Intermediate",
+        (30, 16, 99) => "Expert",
+        _ => "Custom",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(replay: &Replay) -> Vec<(Action, usize, usize, u64)> {
+        replay.moves.iter().map(|mv| (mv.action, mv.x, mv.y, mv.time)).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = Config::new(4, 4, 2);
+        let mut game = Game::with_mines(config, Mode::Imported, 0, &[(0, 0), (0, 3)]);
+        let mut status = Status::Game;
+        let played = [
+            (Action::Reveal, 3, 3, 120),
+            (Action::Flag, 0, 0, 800),
+            (Action::Flag, 0, 3, 1250),
+            (Action::Flag, 0, 3, 1400),
+            (Action::Flag, 0, 3, 2001),
+            (Action::Chord, 1, 1, 3333),
+        ];
+        for (action, x, y, time) in played {
+            game.replay(Move { action, x, y, time }, &mut status).unwrap();
+        }
+        assert!(matches!(status, Status::Success));
+
+        let replay = Replay::from_game(&game);
+        let parsed = parse(&export(&replay, "tester")).unwrap();
+        let Config { col, row, mine } = parsed.config;
+        assert_eq!((col, row, mine), (4, 4, 2));
+        assert_eq!(parsed.mines, replay.mines);
+        assert!(events(&parsed) == played);
+    }
+}
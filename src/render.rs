@@ -1,12 +1,21 @@
 use crossterm::{
     style::Stylize,
 };
-use std::time::Instant;
+use std::time::Duration;
 
 
 use crate::app::*;
+use crate::analysis::*;
+use crate::code::*;
+use crate::config::*;
+use crate::date;
 use crate::error::*;
+use crate::game::*;
+use crate::metrics::*;
+use crate::profile;
+use crate::scores::*;
 use crate::screen::*;
+use crate::solver::*;
 
 pub fn render(app: &App) -> Result<(), RenderError> {
     let box_x = 0;
@@ -18,36 +27,85 @@ pub fn render(app: &App) -> Result<(), RenderError> {
             app.print(&get_banner());
             app.print("\n\n\n\n");
             app.print(&get_manual());
+            app.print(&get_profiles(app));
+            app.print(&get_presets(app));
             app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
-            box_y += 18;
+            box_y += 25;
+            if !app.presets.is_empty() {
+                box_y += app.presets.len() + 1;
+            }
         }
         Status::Game => {
             let game = app.game.as_ref().ok_or(RenderError::NoGame)?;
             game.draw(game.draw_mine, &app.screen);
-            app.print("\n\n\n");
-            app.print(&"Input position: <X> <Y>\n".green().to_string());
-            app.print(&"Enter q back to the menu\n".green().to_string());
+            app.print(&format!("{}\n{}\n\n", get_game_info(app, game), get_hint_reason(game)));
+            app.print(&"Input position: <X> <Y>, flag: f <X> <Y>, chord: c <X> <Y>\n".green().to_string());
+            app.print(&"Enter hint for a hint, prob [<X> <Y>] for mine probabilities, bot [<moves per second>] to let the bot play\n".green().to_string());
+            app.print(&"Enter save to save, export <file> to save the board, q to save and back to the menu\n".green().to_string());
             app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
-            box_y += game.config.row + 8;
+            box_y += game.config.row + 9;
         }
-        Status::Success => {
-            let now = Instant::now();
+        Status::Success | Status::Failed => {
             let game = app.game.as_ref().ok_or(RenderError::NoGame)?;
-            let dura = now - game.start; 
             game.draw(game.draw_mine, &app.screen);
             app.print("\n\n\n\n");
-            app.print(&"You Win!\n".green().to_string());
-            app.print(&format!("Use time: {}\n", format_duration(dura)).green().to_string());
-            app.print(&"Enter q back to the menu\n".green().to_string());
-            box_y += game.config.row + 9;
+            let summary = get_summary(app, game);
+            app.print(&summary);
+            app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
+            box_y += game.config.row + 8 + summary.lines().count();
         }
-        Status::Failed => {
-            let game = app.game.as_ref().ok_or(RenderError::NoGame)?;
+        Status::Replay => {
+            let viewer = app.viewer.as_ref().ok_or(RenderError::NoGame)?;
+            let game = &viewer.game;
             game.draw(game.draw_mine, &app.screen);
+            app.print("\n\n\n");
+            app.print(
+                &format!(
+                    "Replay {} / {}  move {}/{}  speed {}x  {}\n",
+                    format_duration(Duration::from_millis(viewer.clock)),
+                    format_duration(Duration::from_millis(viewer.replay.duration())),
+                    viewer.pos,
+                    viewer.replay.moves.len(),
+                    viewer.speed(),
+                    if viewer.playing { "playing" } else { "paused" },
+                )
+                .cyan()
+                .to_string(),
+            );
+            app.print(&"Enter p (or nothing) to play/pause, n/b to step forward/back, +/- to change speed\n".green().to_string());
+            app.print(&"Enter <seconds> to seek, s/e to jump to start/end, export-raw <file> to save as RAW, q back to the menu\n".green().to_string());
+            app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
+            box_y += game.config.row + 9;
+        }
+        Status::Stats => {
+            let stats = get_stats(app);
+            app.print(&stats);
+            app.print("\n");
+            app.print(&"Enter reset-stats to clear all statistics, q back to the menu\n".green().to_string());
+            app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
+            box_y += stats.lines().count() + 3;
+        }
+        Status::Scores => {
+            let mut scores = String::new();
+            for (mode, table) in app.scores.iter() {
+                scores += &get_score_table(mode, table, None);
+                scores += "\n";
+            }
+            if scores.is_empty() {
+                scores += "No high scores yet\n\n";
+            }
+            app.print(&scores);
+            app.print(&"Enter q back to the menu\n".green().to_string());
+            app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
+            box_y += scores.lines().count() + 2;
+        }
+        Status::Restore => {
+            app.print(&get_banner());
             app.print("\n\n\n\n");
-            app.print(&"You Lose!\n".red().to_string());
-            app.print(&"Enter q back to the menu\n".red().to_string());
-            box_y += game.config.row + 8;
+            app.print(&"An interrupted game was found.\n".yellow().to_string());
+            app.print(&"Enter y to restore it, n to discard it\n".yellow().to_string());
+            app.print(&format!("{}\n", app.input.error_msg).dark_red().to_string());
+            box_y += 15;
         }
     }
     draw_box(&app.screen, box_x, box_y as u16, box_w, box_h);
@@ -89,15 +147,331 @@ pub fn draw_text_in_box(screen: &Screen, x: u16, y: u16, width: u16, text: &str)
 }
 
 fn get_manual() -> String {
-    format!("{}\n{}\n{}\n{}\n{}\n", 
+    format!("{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n", 
         "Enter e to select EASY mode (8 x 8 x 10)".green(),
         "Enter n to select NORMAL mode (16 x 16 x 40)".blue(),
         "Enter h to select HARD mode (16 x 30 x 99)".red(),
         "Enter c:<width> <height> <mines> to custom size and mine's number mode".yellow(),
+        "Enter d:e, d:n or d:h to play today's EASY, NORMAL or HARD daily challenge, or paste a TM- board code".yellow(),
+        "Enter p:<name> to play a preset, save-preset <name> <width> <height> <mines> to save one".cyan(),
+        "Enter load to resume the saved game, replay [file] to watch the last (or given) replay".white(),
+        "Enter s for statistics, l for high scores, profile <name> to switch profile, name <player> to rename".white(),
+        "Enter import <file> to play a board file, export <file> / export-raw <file> to save the last board / replay".white(),
+        "Enter hint-limit <n> to allow n hints per game, hint-limit off for no limit".white(),
         "Enter q to QUIT game".magenta(),
     )
 }
 
+fn get_profiles(app: &App) -> String {
+    let others: Vec<String> = profile::list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| *p != app.profile)
+        .collect();
+    let mut s = format!("Profile: {} ({})", app.profile.clone().bold(), app.player());
+    if !others.is_empty() {
+        s += &format!("  others: {}", others.join(", "));
+    }
+    format!("{}\n", s.cyan())
+}
+
+fn get_presets(app: &App) -> String {
+    if app.presets.is_empty() {
+        return String::new();
+    }
+    let mut s = format!("{}\n", "Presets:".cyan());
+    for (name, cfg) in app.presets.iter() {
+        let (played, won) = app
+            .stats
+            .get(&Mode::Preset(name.clone()).key())
+            .map_or((0, 0), |stats| (stats.played, stats.won));
+        s += &format!(
+            "  {} ({} x {} x {})  played {}, won {}\n",
+            name, cfg.col, cfg.row, cfg.mine, played, won
+        );
+    }
+    s
+}
+
+fn get_game_info(app: &App, game: &Game) -> String {
+    let mut info = match &game.mode {
+        Mode::Daily { level, day } => format!("Daily challenge {} {}", date::format_day(*day), level),
+        _ => String::new(),
+    };
+    if game.practice {
+        info += " (practice, not counted)";
+    } else if game.assisted {
+        info += " (assisted, not counted)";
+    }
+    if game.overlay
+        && let Some((x, y)) = game.focus
+        && let Some(p) = probabilities(&Position::from_game(game)).get(x, y)
+    {
+        info += &format!("  Mine probability at {} {}: {:.1}%", x + 1, y + 1, p * 100.0);
+    }
+    if let Some(bot) = &app.bot {
+        info += &format!("  Bot playing at {} moves per second, enter bot to stop", bot.speed);
+    }
+    if let Some(hint) = &game.hint {
+        info += &if hint.safe {
+            format!("  Hint: {} {} is safe", hint.x + 1, hint.y + 1)
+        } else {
+            format!(
+                "  Hint: no safe cell, {} {} is the least risky at {:.1}%",
+                hint.x + 1,
+                hint.y + 1,
+                hint.probability * 100.0
+            )
+        };
+    }
+    info.trim().yellow().to_string()
+}
+
+/// Why the hinted cell was picked, the cells it depends on are highlighted.
+fn get_hint_reason(game: &Game) -> String {
+    let Some(hint) = &game.hint else {
+        return String::new();
+    };
+    let reason = hint.explain(&Position::from_game(game));
+    let mut chars = reason.chars();
+    let reason = chars.next().map_or(String::new(), |c| c.to_uppercase().chain(chars).collect());
+    reason.yellow().to_string()
+}
+
+fn get_summary(app: &App, game: &Game) -> String {
+    let won = matches!(app.status, Status::Success);
+    let dura = game.elapsed();
+    let m = game.metrics();
+    let c = game.clicks;
+    let solved = if won { m.bbbv } else { game.bbbv_cleared() };
+
+    let mut title = if won {
+        "You Win!".green().to_string()
+    } else {
+        "You Lose!".red().to_string()
+    };
+    if game.practice {
+        title += &" (practice, not counted)".yellow().to_string();
+    } else if game.hints > 0 {
+        title += &format!(" (hints used: {}, not counted)", game.hints).yellow().to_string();
+    } else if game.assisted {
+        title += &" (assisted, not counted)".yellow().to_string();
+    }
+    let ms = dura.as_millis() as u64;
+    let best = match app.previous_best {
+        Some(best) if won && game.counted() && ms < best => " (new personal best!)".to_string(),
+        Some(best) if won && ms >= best => format!(
+            " (personal best {}, +{})",
+            format_duration(Duration::from_millis(best)),
+            format_duration(dura - Duration::from_millis(best))
+        ),
+        Some(best) => format!(" (personal best {})", format_duration(Duration::from_millis(best))),
+        None if won && game.counted() => " (new personal best!)".to_string(),
+        None => String::new(),
+    };
+    let progress = if won {
+        String::new()
+    } else {
+        format!(
+            "  Progress: {:.0}%",
+            solved as f64 * 100.0 / m.bbbv.max(1) as f64
+        )
+    };
+
+    let mut actions = format!(
+        "{}\n",
+        "Enter r to retry this board, g for a new game with the same settings, s <file> to save the replay".green()
+    );
+    actions += &format!(
+        "{}\n",
+        "Enter share (or share ascii) for a spoiler-free result card, review to go over the risks you took".green()
+    );
+    if !won {
+        actions += &format!(
+            "{}\n",
+            "Enter u to continue from before the fatal move (practice)".green()
+        );
+    }
+
+    let table = match app.last_rank {
+        Some(rank) => get_score_table(&game.key(), app.scores.table(&game.key()), Some(rank)),
+        None => String::new(),
+    };
+
+    let code = match BoardCode::from_game(game) {
+        Ok(code) => format!("{}\n", format!("Board code: {}", code.encode()).magenta()),
+        Err(_) => String::new(),
+    };
+
+    let card = app.card.as_ref().map_or(String::new(), |card| format!("\n{}\n", card));
+    let review = app.review.as_ref().map_or(String::new(), |r| get_review(r));
+
+    format!(
+        "{}\n{}\n{}\n{}\n{}{}{}{}{}{}\n",
+        title,
+        format!("Time: {}{}", format_duration(dura), best).cyan(),
+        format!(
+            "3BV: {}/{} (openings {}, islands {}, ZiNi {})  3BV/s: {:.2}{}",
+            solved,
+            m.bbbv,
+            m.openings,
+            m.islands,
+            m.zini,
+            bbbv_per_second(solved, dura.as_secs_f64()),
+            progress,
+        )
+        .cyan(),
+        format!(
+            "Clicks: {} (left {}, right {}, chord {}, wasted {})  Efficiency (IOE): {:.2}  Correctness: {:.0}%",
+            c.total(),
+            c.left,
+            c.right,
+            c.chord,
+            c.wasted,
+            ioe(solved, &c),
+            c.correctness() * 100.0,
+        )
+        .cyan(),
+        code,
+        table,
+        card,
+        review,
+        actions,
+        "Enter q back to the menu".green(),
+    )
+}
+
+/// Most risky moves listed in a review, the rest are only counted.
+const REVIEW_LINES: usize = 10;
+
+fn get_review(reviewed: &[Reviewed]) -> String {
+    let guesses = reviewed.iter().filter(|r| matches!(r.class, Class::Guess(_))).count();
+    let risks = reviewed.iter().filter(|r| matches!(r.class, Class::Risk { .. })).count();
+    let mut s = format!(
+        "\n{}\n",
+        format!(
+            "Review: {} moves opened cells, {} safe, {} forced guesses, {} avoidable risks",
+            reviewed.len(),
+            reviewed.len() - guesses - risks,
+            guesses,
+            risks
+        )
+        .cyan()
+    );
+    let risky: Vec<&Reviewed> = reviewed.iter().filter(|r| !matches!(r.class, Class::Safe)).collect();
+    for r in risky.iter().take(REVIEW_LINES) {
+        let cell = format!("{},{}", r.mv.x + 1, r.mv.y + 1);
+        let line = match r.class {
+            Class::Guess(p) => format!("forced guess at {}, {:.1}% chance of a mine", cell, p * 100.0),
+            Class::Risk { probability, safe } => format!(
+                "avoidable risk at {}, {:.1}% chance of a mine while {},{} was provably safe",
+                cell,
+                probability * 100.0,
+                safe.0 + 1,
+                safe.1 + 1
+            ),
+            Class::Safe => continue,
+        };
+        let line = format!(
+            "  Move {} ({}): {}{}",
+            r.index,
+            format_duration(Duration::from_millis(r.mv.time)),
+            line,
+            if r.fatal { ", hit a mine" } else { "" }
+        );
+        s += &format!("{}\n", if r.fatal { line.red() } else { line.yellow() });
+    }
+    if risky.len() > REVIEW_LINES {
+        s += &format!("  ... and {} more\n", risky.len() - REVIEW_LINES);
+    }
+    s
+}
+
+fn get_stats(app: &App) -> String {
+    let mut s = format!(
+        "{}\n",
+        format!(
+            "{:<20} {:>6} {:>5} {:>5} {:>6} {:>7} {:>12} {:>10} {:>10} {:>11}",
+            "Mode", "Played", "Won", "Lost", "Win%", "Streak", "Best streak", "Best time", "Avg time", "Best 3BV/s"
+        )
+        .cyan()
+    );
+    let time = |ms: Option<u64>| ms.map_or("-".to_string(), |ms| format_duration(Duration::from_millis(ms)));
+    for (mode, stats) in app.stats.iter() {
+        s += &format!(
+            "{:<20} {:>6} {:>5} {:>5} {:>5.1}% {:>7} {:>12} {:>10} {:>10} {:>11.2}\n",
+            mode,
+            stats.played,
+            stats.won,
+            stats.lost(),
+            stats.win_rate() * 100.0,
+            stats.streak,
+            stats.best_streak,
+            time(stats.best),
+            time(stats.average()),
+            stats.best_bbbv_per_second,
+        );
+    }
+    if app.stats.iter().next().is_none() {
+        s += "No games played yet\n";
+    }
+
+    let today = date::day();
+    let mut daily = app.stats.daily().peekable();
+    if daily.peek().is_some() {
+        s += &format!(
+            "\n{}\n",
+            format!(
+                "{:<20} {:>6} {:>5} {:>7} {:>12} {:>11}",
+                "Daily", "Played", "Won", "Streak", "Best streak", "Today"
+            )
+            .cyan()
+        );
+    }
+    for (level, stats) in daily {
+        let done = stats.last_played.is_some_and(|day| day >= today);
+        s += &format!(
+            "{:<20} {:>6} {:>5} {:>7} {:>12} {:>11}\n",
+            level,
+            stats.played,
+            stats.won,
+            stats.current_streak(today),
+            stats.best_streak,
+            if done { "played" } else { "available" },
+        );
+    }
+    s
+}
+
+fn get_score_table(mode: &str, table: &[Score], highlight: Option<usize>) -> String {
+    let mut s = format!("{}\n", format!("High scores: {}", mode).magenta());
+    s += &format!(
+        "{}\n",
+        format!(
+            "{:>4} {:>10} {:>8} {:>10} {:<16} {:>20}",
+            "#", "Time", "3BV/s", "Date", "Player", "Seed"
+        )
+        .cyan()
+    );
+    for (i, score) in table.iter().enumerate() {
+        let line = format!(
+            "{:>4} {:>10} {:>8.2} {:>10} {:<16} {:>20}",
+            i + 1,
+            format_duration(Duration::from_millis(score.time)),
+            score.bbbv_per_second,
+            score.date,
+            score.player,
+            score.seed,
+        );
+        if highlight == Some(i) {
+            s += &format!("{}\n", line.black().on_yellow());
+        } else {
+            s += &format!("{}\n", line);
+        }
+    }
+    s
+}
+
 fn get_banner() -> String {
     r#"
 ████████╗   ███╗   ███╗██╗███╗   ██╗███████╗███████╗██╗    ██╗███████╗███████╗██████╗ ███████╗██████╗ 
@@ -110,7 +484,7 @@ fn get_banner() -> String {
     "#.to_string()
 }
 
-fn format_duration(d: std::time::Duration) -> String {
+pub fn format_duration(d: std::time::Duration) -> String {
     let total_secs = d.as_secs();
     let hours = total_secs / 3600;
     let minutes = (total_secs % 3600) / 60;
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::*;
+use crate::error::{ReplayError, StorageError};
+use crate::game::*;
+use crate::raw;
+use crate::profile;
+use crate::video;
+
+const REPLAY_DIR: &str = "replays";
+
+/// A finished game: the board it was played on and every move with its timestamp.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: Config,
+    pub mode: Mode,
+    pub seed: u64,
+    pub mines: Vec<(usize, usize)>,
+    pub moves: Vec<Move>,
+}
+
+impl Replay {
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            config: game.config,
+            mode: game.mode.clone(),
+            seed: game.seed,
+            mines: game.mines(),
+            moves: game.moves.clone(),
+        }
+    }
+
+    /// The board before the first move.
+    pub fn to_game(&self) -> Game {
+        Game::with_mines(self.config, self.mode.clone(), self.seed, &self.mines)
+    }
+
+    /// Length of the replay in milliseconds.
+    pub fn duration(&self) -> u64 {
+        self.moves.last().map_or(0, |mv| mv.time)
+    }
+
+    /// Save into the profile's replay directory, named after the current time.
+    pub fn save(&self, profile: &str) -> Result<PathBuf, StorageError> {
+        let dir = profile::dir(profile)?.join(REPLAY_DIR);
+        fs::create_dir_all(&dir)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = dir.join(format!("{}.json", now));
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, StorageError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Open a replay of any known format, telling them apart by extension.
+    pub fn open(path: &Path) -> Result<Self, ReplayError> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match ext.as_str() {
+            "raw" | "txt" => raw::parse(&fs::read_to_string(path)?),
+            "avf" => video::parse_avf(&fs::read(path)?),
+            "rmv" => video::parse_rmv(&fs::read(path)?),
+            _ => Ok(Self::load(path)?),
+        }
+    }
+
+    /// Replays saved by a profile, oldest first.
+    pub fn list(profile: &str) -> Result<Vec<PathBuf>, StorageError> {
+        let dir = profile::dir(profile)?.join(REPLAY_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+}
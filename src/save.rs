@@ -0,0 +1,71 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::*;
+use crate::error::StorageError;
+use crate::game::Move;
+use crate::metrics::Clicks;
+use crate::profile;
+use crate::storage;
+
+const SAVE_FILE: &str = "save.json";
+
+/// Everything needed to resume a game in a later session.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub config: Config,
+    pub mode: Mode,
+    pub seed: u64,
+    /// Whether the mines are still waiting for the first click.
+    pub first: bool,
+    /// Index of the move the mines were placed around.
+    #[serde(default)]
+    pub safe_click: Option<usize>,
+    pub mines: Vec<(usize, usize)>,
+    pub board: Vec<Vec<bool>>,
+    pub flags: Vec<Vec<bool>>,
+    /// Elapsed milliseconds.
+    pub elapsed: u64,
+    pub moves: Vec<Move>,
+    #[serde(default)]
+    pub clicks: Clicks,
+    #[serde(default)]
+    pub practice: bool,
+    #[serde(default)]
+    pub assisted: bool,
+    #[serde(default)]
+    pub hints: u32,
+}
+
+impl SavedGame {
+    pub fn save(&self, profile: &str) -> Result<(), StorageError> {
+        storage::save(&profile::file(profile, SAVE_FILE), self)
+    }
+
+    /// Load the profile's saved game, removing it from disk so it can only be
+    /// resumed once.
+    pub fn take(profile: &str) -> Result<Option<Self>, StorageError> {
+        let path = storage::data_dir()?.join(profile::file(profile, SAVE_FILE));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let saved = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_file(path)?;
+        Ok(Some(saved))
+    }
+
+    /// Remove the saved game if it is a snapshot of the game with this seed,
+    /// so a finished game can't be loaded again.
+    pub fn discard(profile: &str, seed: u64) -> Result<(), StorageError> {
+        let path = storage::data_dir()?.join(profile::file(profile, SAVE_FILE));
+        if !path.exists() {
+            return Ok(());
+        }
+        let saved: Self = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        if saved.seed == seed {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
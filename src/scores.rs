@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::profile;
+use crate::storage;
+
+const SCORES_FILE: &str = "scores.json";
+
+/// Number of entries kept per table.
+pub const TOP: usize = 10;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Score {
+    /// Winning time in milliseconds.
+    pub time: u64,
+    pub bbbv_per_second: f64,
+    pub date: String,
+    pub player: String,
+    pub seed: u64,
+}
+
+/// The fastest wins of every mode.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<Score>>,
+}
+
+impl HighScores {
+    pub fn load(profile: &str) -> Result<Self, StorageError> {
+        storage::load(&profile::file(profile, SCORES_FILE))
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), StorageError> {
+        storage::save(&profile::file(profile, SCORES_FILE), self)
+    }
+
+    pub fn table(&self, key: &str) -> &[Score] {
+        self.tables.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<Score>)> {
+        self.tables.iter()
+    }
+
+    /// Add a win, returning its rank if it made the table.
+    pub fn insert(&mut self, key: &str, score: Score) -> Option<usize> {
+        let table = self.tables.entry(key.to_string()).or_default();
+        let rank = table.iter().position(|s| score.time < s.time).unwrap_or(table.len());
+        if rank >= TOP {
+            return None;
+        }
+        table.insert(rank, score);
+        table.truncate(TOP);
+        Some(rank)
+    }
+}
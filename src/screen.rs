@@ -85,3 +85,9 @@ impl Screen {
         Ok(())
     }
 }
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
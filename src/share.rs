@@ -0,0 +1,43 @@
+use crate::config::*;
+use crate::date;
+use crate::game::*;
+use crate::metrics::*;
+use crate::render::format_duration;
+
+/// A spoiler-free card of a finished game to paste into chat: which squares
+/// were opened, never what was under them.
+pub fn card(game: &Game, won: bool, ascii: bool) -> String {
+    let Config { col, row, mine } = game.config;
+    let (open, hidden, result) = if ascii {
+        ("#", ".", if won { "won" } else { "lost" })
+    } else {
+        ("🟩", "⬜", if won { "✅" } else { "💥" })
+    };
+
+    let title = match &game.mode {
+        Mode::Daily { level, day } => format!("Daily {} {}", date::format_day(*day), level),
+        mode => format!("{} {}x{}/{}", mode.key(), col, row, mine),
+    };
+    let dura = game.elapsed();
+    let m = game.metrics();
+    let solved = if won { m.bbbv } else { game.bbbv_cleared() };
+
+    let mut s = format!("t-minesweeper {} {}\n", title, result);
+    s += &format!(
+        "Time {}  3BV {}/{}  3BV/s {:.2}\n",
+        format_duration(dura),
+        solved,
+        m.bbbv,
+        bbbv_per_second(solved, dura.as_secs_f64()),
+    );
+    if !matches!(game.mode, Mode::Daily { .. }) {
+        s += &format!("Seed {}\n", game.seed);
+    }
+    for x in 0..row {
+        for y in 0..col {
+            s += if game.revealed(x, y) { open } else { hidden };
+        }
+        s.push('\n');
+    }
+    s
+}
@@ -0,0 +1,954 @@
+use crate::game::*;
+
+/// A square as the player sees it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Hidden,
+    Flagged,
+    Number(u8),
+}
+
+/// The visible state of a board, indexed as `(row, col)`. Flags are trusted
+/// to be mines.
+#[derive(Clone)]
+pub struct Position {
+    cells: Vec<Vec<Cell>>,
+    /// Total number of mines on the board, if known.
+    pub mines: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Safe,
+    Mine,
+}
+
+/// How a deduction was reached.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rule {
+    /// A number whose hidden neighbours are either all safe or all mines.
+    Single,
+    /// A number whose hidden neighbours contain those of another number, so
+    /// the difference holds the difference of their mines.
+    Subset,
+    /// The mines left over once the numbers are accounted for.
+    MineCount,
+}
+
+#[derive(Clone, Debug)]
+pub struct Deduction {
+    pub x: usize,
+    pub y: usize,
+    pub verdict: Verdict,
+    pub rule: Rule,
+    /// The numbers the deduction follows from.
+    pub sources: Vec<(usize, usize)>,
+    /// Flags and cells found to be mines earlier that the numbers count on.
+    pub mines: Vec<(usize, usize)>,
+}
+
+impl Deduction {
+    /// The reasoning in plain words, positions written as `X,Y`.
+    pub fn explain(&self, pos: &Position) -> String {
+        let outcome = match self.verdict {
+            Verdict::Safe => format!("{} is safe", format_cell((self.x, self.y))),
+            Verdict::Mine => format!("{} is a mine", format_cell((self.x, self.y))),
+        };
+        let number = |s: (usize, usize)| match pos.get(s.0, s.1) {
+            Cell::Number(n) => format!("the {} at {}", n, format_cell(s)),
+            _ => format_cell(s),
+        };
+        let around = |s: (usize, usize)| -> Vec<(usize, usize)> {
+            let near = pos.neighbours(s.0, s.1);
+            self.mines.iter().filter(|m| near.contains(m)).copied().collect()
+        };
+        let left = |s: (usize, usize)| match pos.get(s.0, s.1) {
+            Cell::Number(n) => n as usize - around(s).len().min(n as usize),
+            _ => 0,
+        };
+
+        match (self.rule, self.sources.as_slice()) {
+            (Rule::Single, &[s]) if self.verdict == Verdict::Safe && around(s).is_empty() => {
+                format!("{} has no mines around it, so {}", number(s), outcome)
+            }
+            (Rule::Single, &[s]) if self.verdict == Verdict::Safe => format!(
+                "{} already touches {}, so {}",
+                number(s),
+                describe_mines(pos, &around(s)),
+                outcome
+            ),
+            (Rule::Single, &[s]) => format!(
+                "{} needs {} and has only that many hidden cells left, so {}",
+                number(s),
+                count_mines(left(s)),
+                outcome
+            ),
+            (Rule::Subset, &[b, a]) if self.verdict == Verdict::Safe => format!(
+                "{} needs {} among cells {} also touches, and that is all {} needs, so {}",
+                number(a),
+                count_mines(left(a)),
+                number(b),
+                number(b),
+                outcome
+            ),
+            (Rule::Subset, &[b, a]) => format!(
+                "{} needs {} but only {} fit next to {}, so the rest go on its other cells and {}",
+                number(b),
+                count_mines(left(b)),
+                left(a),
+                number(a),
+                outcome
+            ),
+            (Rule::Subset, sources) => format!(
+                "taken together, the numbers at {} mean {}",
+                join(&sources.iter().map(|&s| format_cell(s)).collect::<Vec<_>>()),
+                outcome
+            ),
+            (Rule::MineCount, _) if self.verdict == Verdict::Safe => format!(
+                "the numbers account for every mine left on the board, so {}",
+                outcome
+            ),
+            (Rule::MineCount, _) => format!(
+                "the mines left on the board only fit on the cells no number touches, so {}",
+                outcome
+            ),
+            (Rule::Single, _) => outcome,
+        }
+    }
+
+    /// Every cell the reasoning relies on: the numbers and the known mines.
+    pub fn involved(&self) -> Vec<(usize, usize)> {
+        self.sources.iter().chain(&self.mines).copied().collect()
+    }
+}
+
+/// Why a cell no rule reaches is safe all the same.
+pub fn explain_exact(cell: (usize, usize)) -> String {
+    format!(
+        "no single rule shows it, but every way the mines can lie around the numbers leaves {} empty",
+        format_cell(cell)
+    )
+}
+
+fn format_cell((x, y): (usize, usize)) -> String {
+    format!("{},{}", x + 1, y + 1)
+}
+
+fn count_mines(n: usize) -> String {
+    match n {
+        1 => "1 more mine".to_string(),
+        n => format!("{} more mines", n),
+    }
+}
+
+/// "the flag at 4,6 and the mine at 5,7", a mine being a hidden cell that was
+/// found earlier.
+fn describe_mines(pos: &Position, mines: &[(usize, usize)]) -> String {
+    let names: Vec<String> = mines
+        .iter()
+        .map(|&(x, y)| match pos.get(x, y) {
+            Cell::Flagged => format!("the flag at {}", format_cell((x, y))),
+            _ => format!("the mine at {}", format_cell((x, y))),
+        })
+        .collect();
+    join(&names)
+}
+
+/// "a", "a and b", "a, b and c".
+fn join(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// The cell the solver would open next.
+#[derive(Clone, Debug)]
+pub struct Hint {
+    pub x: usize,
+    pub y: usize,
+    /// Chance that the cell is a mine, zero when it is proven safe.
+    pub probability: f64,
+    /// Whether the cell is certainly safe.
+    pub safe: bool,
+    /// How the cell was proven safe, if a rule shows it.
+    pub deduction: Option<Deduction>,
+}
+
+impl Hint {
+    /// Why the cell was picked, positions written as `X,Y`.
+    pub fn explain(&self, pos: &Position) -> String {
+        match &self.deduction {
+            Some(d) => d.explain(pos),
+            None if self.safe => explain_exact((self.x, self.y)),
+            None => "no cell can be proven safe, so this is the one least likely to hide a mine".to_string(),
+        }
+    }
+}
+
+/// Hidden cells holding an exact number of mines.
+#[derive(Clone)]
+struct Constraint {
+    cells: Vec<(usize, usize)>,
+    mines: i32,
+    sources: Vec<(usize, usize)>,
+    /// Mines around the sources already taken out of `cells`.
+    known: Vec<(usize, usize)>,
+    rule: Rule,
+}
+
+impl Position {
+    pub fn new(cells: Vec<Vec<Cell>>, mines: Option<usize>) -> Self {
+        Self { cells, mines }
+    }
+
+    pub fn from_game(game: &Game) -> Self {
+        let cfg = game.config;
+        let mut cells = vec![vec![Cell::Hidden; cfg.col]; cfg.row];
+        for (x, line) in cells.iter_mut().enumerate() {
+            for (y, cell) in line.iter_mut().enumerate() {
+                if let Some(n) = game.number(x, y) {
+                    *cell = Cell::Number(n);
+                } else if game.flagged(x, y) {
+                    *cell = Cell::Flagged;
+                }
+            }
+        }
+        Self::new(cells, Some(cfg.mine.max(0) as usize))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[x][y]
+    }
+
+    /// Positions around a cell that lie on the board.
+    pub fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(8);
+        for i in x.saturating_sub(1)..=(x + 1).min(self.rows() - 1) {
+            for j in y.saturating_sub(1)..=(y + 1).min(self.cols() - 1) {
+                if (i, j) != (x, y) {
+                    cells.push((i, j));
+                }
+            }
+        }
+        cells
+    }
+
+    /// All cells that are neither revealed nor flagged.
+    pub fn hidden(&self) -> Vec<(usize, usize)> {
+        self.cells_where(|c| c == Cell::Hidden)
+    }
+
+    pub fn flags(&self) -> usize {
+        self.cells_where(|c| c == Cell::Flagged).len()
+    }
+
+    fn cells_where(&self, f: impl Fn(Cell) -> bool) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (x, line) in self.cells.iter().enumerate() {
+            for (y, &cell) in line.iter().enumerate() {
+                if f(cell) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    /// One constraint per number that still touches hidden cells.
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for (x, line) in self.cells.iter().enumerate() {
+            for (y, &cell) in line.iter().enumerate() {
+                let Cell::Number(n) = cell else {
+                    continue;
+                };
+                let around = self.neighbours(x, y);
+                let flags: Vec<_> = around
+                    .iter()
+                    .filter(|&&(i, j)| self.cells[i][j] == Cell::Flagged)
+                    .copied()
+                    .collect();
+                let cells: Vec<_> = around
+                    .into_iter()
+                    .filter(|&(i, j)| self.cells[i][j] == Cell::Hidden)
+                    .collect();
+                if !cells.is_empty() {
+                    constraints.push(Constraint {
+                        cells,
+                        mines: n as i32 - flags.len() as i32,
+                        sources: vec![(x, y)],
+                        known: flags,
+                        rule: Rule::Single,
+                    });
+                }
+            }
+        }
+        constraints
+    }
+}
+
+impl Constraint {
+    /// The verdict for every cell, when the count settles them all.
+    fn verdict(&self) -> Option<Verdict> {
+        if self.mines == 0 {
+            Some(Verdict::Safe)
+        } else if self.mines == self.cells.len() as i32 {
+            Some(Verdict::Mine)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the count is possible at all, a wrong flag can break it.
+    fn consistent(&self) -> bool {
+        (0..=self.cells.len() as i32).contains(&self.mines)
+    }
+
+    fn contains(&self, other: &Constraint) -> bool {
+        other.cells.iter().all(|c| self.cells.contains(c))
+    }
+
+    /// What is left of `self` once `other`, which it contains, is taken out.
+    fn minus(&self, other: &Constraint) -> Constraint {
+        let union = |a: &[(usize, usize)], b: &[(usize, usize)]| {
+            let mut all = a.to_vec();
+            all.extend(b.iter().filter(|c| !a.contains(c)));
+            all
+        };
+        Constraint {
+            cells: self
+                .cells
+                .iter()
+                .filter(|c| !other.cells.contains(c))
+                .copied()
+                .collect(),
+            mines: self.mines - other.mines,
+            sources: union(&self.sources, &other.sources),
+            known: union(&self.known, &other.known),
+            rule: Rule::Subset,
+        }
+    }
+}
+
+/// Every cell that is provably safe or provably a mine, in the order found.
+pub fn solve(pos: &Position) -> Vec<Deduction> {
+    let mut known = vec![vec![None; pos.cols()]; pos.rows()];
+    let mut deductions = Vec::new();
+    let mut constraints = pos.constraints();
+
+    loop {
+        reduce(&mut constraints, &known);
+        let mut found: Vec<Constraint> = constraints
+            .iter()
+            .filter(|c| c.verdict().is_some())
+            .cloned()
+            .collect();
+
+        if found.is_empty() {
+            let mut derived: Vec<Constraint> = Vec::new();
+            for a in &constraints {
+                for b in &constraints {
+                    if a.cells.len() >= b.cells.len() || !b.contains(a) {
+                        continue;
+                    }
+                    let d = b.minus(a);
+                    if !d.consistent() {
+                        continue;
+                    }
+                    if d.verdict().is_some() {
+                        found.push(d);
+                    } else if !constraints.iter().chain(&derived).any(|c| c.cells == d.cells) {
+                        derived.push(d);
+                    }
+                }
+            }
+            if found.is_empty() && derived.is_empty() {
+                found.extend(mine_count(pos, &constraints, &known));
+                if found.is_empty() {
+                    break;
+                }
+            }
+            constraints.extend(derived);
+        }
+
+        for c in found {
+            let verdict = c.verdict().unwrap();
+            for &(x, y) in &c.cells {
+                if known[x][y].is_none() {
+                    known[x][y] = Some(verdict);
+                    deductions.push(Deduction {
+                        x,
+                        y,
+                        verdict,
+                        rule: c.rule,
+                        sources: c.sources.clone(),
+                        mines: c.known.clone(),
+                    });
+                }
+            }
+        }
+    }
+    deductions
+}
+
+/// Take settled cells out of the constraints and drop the ones left empty.
+fn reduce(constraints: &mut Vec<Constraint>, known: &[Vec<Option<Verdict>>]) {
+    for c in constraints.iter_mut() {
+        let mines: Vec<_> = c
+            .cells
+            .iter()
+            .filter(|&&(x, y)| known[x][y] == Some(Verdict::Mine))
+            .copied()
+            .collect();
+        c.mines -= mines.len() as i32;
+        c.known.extend(mines);
+        c.cells.retain(|&(x, y)| known[x][y].is_none());
+    }
+    constraints.retain(|c| !c.cells.is_empty() && c.consistent());
+}
+
+/// When numbers that share no cells account for every remaining mine, the
+/// cells outside them are safe; when they leave exactly as many mines as there
+/// are cells outside, those are all mines.
+fn mine_count(
+    pos: &Position,
+    constraints: &[Constraint],
+    known: &[Vec<Option<Verdict>>],
+) -> Option<Constraint> {
+    let total = pos.mines? as i32;
+    let found = known.iter().flatten().filter(|&&k| k == Some(Verdict::Mine)).count();
+    let left = total - pos.flags() as i32 - found as i32;
+
+    let mut covered: Vec<(usize, usize)> = Vec::new();
+    let mut mines = 0;
+    let mut sources = Vec::new();
+    for c in constraints {
+        if c.cells.iter().all(|cell| !covered.contains(cell)) {
+            covered.extend(&c.cells);
+            mines += c.mines;
+            sources.extend(&c.sources);
+        }
+    }
+    let rest = Constraint {
+        cells: pos
+            .hidden()
+            .into_iter()
+            .filter(|&(x, y)| known[x][y].is_none() && !covered.contains(&(x, y)))
+            .collect(),
+        mines: left - mines,
+        sources,
+        known: Vec::new(),
+        rule: Rule::MineCount,
+    };
+    (!rest.cells.is_empty() && rest.consistent() && rest.verdict().is_some()).then_some(rest)
+}
+
+/// A provably safe cell if there is one, otherwise the least likely mine. A
+/// cell no rule reaches is still safe when its exact probability is zero.
+pub fn hint(pos: &Position) -> Option<Hint> {
+    if let Some(d) = solve(pos).into_iter().find(|d| d.verdict == Verdict::Safe) {
+        return Some(Hint {
+            x: d.x,
+            y: d.y,
+            probability: 0.0,
+            safe: true,
+            deduction: Some(d),
+        });
+    }
+    let probs = probabilities(pos);
+    let (x, y, probability) = probs.safest()?;
+    Some(Hint {
+        x,
+        y,
+        probability,
+        safe: probs.exact && probability == 0.0,
+        deduction: None,
+    })
+}
+
+/// Frontier components with more enumeration steps than this are estimated.
+const MAX_STEPS: usize = 1 << 21;
+
+/// Mine probability of every hidden cell.
+pub struct Probabilities {
+    cells: Vec<Vec<Option<f64>>>,
+    /// Whether the values are exact rather than estimated.
+    pub exact: bool,
+    /// Whether any arrangement of the mines fits the numbers. When none does
+    /// there are no values at all.
+    pub consistent: bool,
+}
+
+impl Probabilities {
+    /// `None` for revealed and flagged cells, and for cells no number touches
+    /// when the total mine count is unknown.
+    pub fn get(&self, x: usize, y: usize) -> Option<f64> {
+        self.cells[x][y]
+    }
+
+    /// The cell least likely to be a mine, the first one on a tie. Cells
+    /// certain to be mines are never offered.
+    pub fn safest(&self) -> Option<(usize, usize, f64)> {
+        let mut best: Option<(usize, usize, f64)> = None;
+        for (x, line) in self.cells.iter().enumerate() {
+            for (y, p) in line.iter().enumerate() {
+                if let Some(p) = *p
+                    && p < 1.0
+                    && best.is_none_or(|(_, _, b)| p < b)
+                {
+                    best = Some((x, y, p));
+                }
+            }
+        }
+        best
+    }
+
+    /// Cells no arrangement puts a mine on. Only exact values are trusted.
+    pub fn safe(&self) -> Vec<(usize, usize)> {
+        if !self.exact {
+            return Vec::new();
+        }
+        let mut safe = Vec::new();
+        for (x, line) in self.cells.iter().enumerate() {
+            for (y, p) in line.iter().enumerate() {
+                if *p == Some(0.0) {
+                    safe.push((x, y));
+                }
+            }
+        }
+        safe
+    }
+}
+
+/// Solutions of one frontier component, grouped by how many mines they place.
+struct Tally {
+    cells: Vec<(usize, usize)>,
+    /// Solutions with `k` mines.
+    counts: Vec<f64>,
+    /// Solutions with `k` mines that put one on each cell.
+    hits: Vec<Vec<f64>>,
+}
+
+/// Mine probabilities from every arrangement consistent with the numbers,
+/// each weighted by the ways to place the remaining mines on the cells no
+/// number touches.
+pub fn probabilities(pos: &Position) -> Probabilities {
+    let mut cells = vec![vec![None; pos.cols()]; pos.rows()];
+    let mut known = vec![vec![None; pos.cols()]; pos.rows()];
+    for d in solve(pos) {
+        known[d.x][d.y] = Some(d.verdict);
+    }
+    let mut constraints = pos.constraints();
+    reduce(&mut constraints, &known);
+
+    let mut found = 0;
+    let mut frontier = Vec::new();
+    let mut interior = Vec::new();
+    for (x, y) in pos.hidden() {
+        match known[x][y] {
+            Some(Verdict::Safe) => cells[x][y] = Some(0.0),
+            Some(Verdict::Mine) => {
+                cells[x][y] = Some(1.0);
+                found += 1;
+            }
+            None if constraints.iter().any(|c| c.cells.contains(&(x, y))) => {
+                frontier.push((x, y))
+            }
+            None => interior.push((x, y)),
+        }
+    }
+    let left = pos
+        .mines
+        .map(|m| m as i32 - pos.flags() as i32 - found);
+
+    let tallies: Option<Vec<Tally>> = components(&frontier, &constraints)
+        .into_iter()
+        .map(|comp| enumerate(comp, &constraints))
+        .collect();
+    let exact = match tallies {
+        Some(tallies) => {
+            if !combine(&tallies, &interior, left, &mut cells) {
+                return Probabilities {
+                    cells: vec![vec![None; pos.cols()]; pos.rows()],
+                    exact: false,
+                    consistent: false,
+                };
+            }
+            true
+        }
+        None => {
+            estimate(&frontier, &interior, &constraints, left, &mut cells);
+            false
+        }
+    };
+    Probabilities {
+        cells,
+        exact,
+        consistent: true,
+    }
+}
+
+/// Split the frontier into groups of cells that share no number.
+fn components(frontier: &[(usize, usize)], constraints: &[Constraint]) -> Vec<Vec<(usize, usize)>> {
+    let mut seen: Vec<(usize, usize)> = Vec::new();
+    let mut components = Vec::new();
+    for &start in frontier {
+        if seen.contains(&start) {
+            continue;
+        }
+        seen.push(start);
+        let mut comp = vec![start];
+        let mut i = 0;
+        while i < comp.len() {
+            let cell = comp[i];
+            for c in constraints.iter().filter(|c| c.cells.contains(&cell)) {
+                for &next in &c.cells {
+                    if !seen.contains(&next) {
+                        seen.push(next);
+                        comp.push(next);
+                    }
+                }
+            }
+            i += 1;
+        }
+        components.push(comp);
+    }
+    components
+}
+
+/// Count the mine arrangements of a component that satisfy its numbers,
+/// giving up once it takes too long.
+fn enumerate(cells: Vec<(usize, usize)>, constraints: &[Constraint]) -> Option<Tally> {
+    let local: Vec<&Constraint> = constraints
+        .iter()
+        .filter(|c| c.cells.iter().any(|cell| cells.contains(cell)))
+        .collect();
+    // The constraints each cell takes part in.
+    let touching: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|cell| {
+            (0..local.len())
+                .filter(|&i| local[i].cells.contains(cell))
+                .collect()
+        })
+        .collect();
+
+    let mut search = Search {
+        touching: &touching,
+        need: local.iter().map(|c| c.mines).collect(),
+        open: local.iter().map(|c| c.cells.len() as i32).collect(),
+        mines: vec![false; cells.len()],
+        steps: 0,
+        tally: Tally {
+            counts: vec![0.0; cells.len() + 1],
+            hits: vec![vec![0.0; cells.len()]; cells.len() + 1],
+            cells,
+        },
+    };
+    search.run(0).then_some(search.tally)
+}
+
+struct Search<'a> {
+    touching: &'a [Vec<usize>],
+    /// Mines each constraint still needs.
+    need: Vec<i32>,
+    /// Cells of each constraint not yet assigned.
+    open: Vec<i32>,
+    mines: Vec<bool>,
+    steps: usize,
+    tally: Tally,
+}
+
+impl Search<'_> {
+    /// Assign cell `i` and everything after it, false when out of steps.
+    fn run(&mut self, i: usize) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return false;
+        }
+        if i == self.mines.len() {
+            let k = self.mines.iter().filter(|&&m| m).count();
+            self.tally.counts[k] += 1.0;
+            for (j, _) in self.mines.iter().enumerate().filter(|&(_, &m)| m) {
+                self.tally.hits[k][j] += 1.0;
+            }
+            return true;
+        }
+        for mine in [false, true] {
+            let fits = self.touching[i].iter().all(|&c| {
+                let need = self.need[c] - mine as i32;
+                need >= 0 && need < self.open[c]
+            });
+            if !fits {
+                continue;
+            }
+            for &c in &self.touching[i] {
+                self.need[c] -= mine as i32;
+                self.open[c] -= 1;
+            }
+            self.mines[i] = mine;
+            let done = self.run(i + 1);
+            for &c in &self.touching[i] {
+                self.need[c] += mine as i32;
+                self.open[c] += 1;
+            }
+            self.mines[i] = false;
+            if !done {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Weigh the component tallies against each other and the unconstrained
+/// cells, false if no arrangement fits the mine count.
+fn combine(
+    tallies: &[Tally],
+    interior: &[(usize, usize)],
+    left: Option<i32>,
+    cells: &mut [Vec<Option<f64>>],
+) -> bool {
+    let frontier: usize = tallies.iter().map(|t| t.cells.len()).sum();
+    let u = interior.len();
+    // Ways to place the rest of the mines off the frontier when it holds `k`,
+    // scaled by the largest so they stay finite.
+    let weights: Vec<f64> = match left {
+        Some(left) => {
+            let ln: Vec<Option<f64>> = (0..=frontier)
+                .map(|k| {
+                    let rest = left - k as i32;
+                    (0..=u as i32).contains(&rest).then(|| ln_choose(u, rest as usize))
+                })
+                .collect();
+            let max = ln.iter().flatten().copied().fold(f64::MIN, f64::max);
+            ln.iter().map(|l| l.map_or(0.0, |l| (l - max).exp())).collect()
+        }
+        None => vec![1.0; frontier + 1],
+    };
+
+    let all = tallies
+        .iter()
+        .fold(vec![1.0], |acc, t| convolve(&acc, &t.counts));
+    let total: f64 = all.iter().zip(&weights).map(|(n, w)| n * w).sum();
+    if total <= 0.0 {
+        return false;
+    }
+
+    for (i, t) in tallies.iter().enumerate() {
+        let others = tallies
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(vec![1.0], |acc, (_, o)| convolve(&acc, &o.counts));
+        for (j, &(x, y)) in t.cells.iter().enumerate() {
+            let mut p = 0.0;
+            for (k, hits) in t.hits.iter().enumerate() {
+                for (m, n) in others.iter().enumerate() {
+                    p += hits[j] * n * weights[k + m];
+                }
+            }
+            cells[x][y] = Some(p / total);
+        }
+    }
+
+    if let Some(left) = left
+        && u > 0
+    {
+        let expected: f64 = all
+            .iter()
+            .zip(&weights)
+            .enumerate()
+            .map(|(k, (n, w))| n * w * (left - k as i32) as f64)
+            .sum();
+        for &(x, y) in interior {
+            cells[x][y] = Some(expected / total / u as f64);
+        }
+    }
+    true
+}
+
+/// Rough probabilities for frontiers too large to enumerate: each cell gets
+/// the average density of the numbers around it and the rest of the mines
+/// are spread evenly over the other cells.
+fn estimate(
+    frontier: &[(usize, usize)],
+    interior: &[(usize, usize)],
+    constraints: &[Constraint],
+    left: Option<i32>,
+    cells: &mut [Vec<Option<f64>>],
+) {
+    let mut expected = 0.0;
+    for &(x, y) in frontier {
+        let densities: Vec<f64> = constraints
+            .iter()
+            .filter(|c| c.cells.contains(&(x, y)))
+            .map(|c| c.mines as f64 / c.cells.len() as f64)
+            .collect();
+        let p = densities.iter().sum::<f64>() / densities.len() as f64;
+        cells[x][y] = Some(p);
+        expected += p;
+    }
+    if let Some(left) = left
+        && !interior.is_empty()
+    {
+        let p = ((left as f64 - expected) / interior.len() as f64).clamp(0.0, 1.0);
+        for &(x, y) in interior {
+            cells[x][y] = Some(p);
+        }
+    }
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+fn ln_choose(n: usize, k: usize) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+fn ln_factorial(n: usize) -> f64 {
+    (2..=n).map(|i| (i as f64).ln()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle;
+
+    fn found(text: &str) -> Vec<(usize, usize, Verdict, Rule)> {
+        solve(&puzzle::parse(text).unwrap())
+            .into_iter()
+            .map(|d| (d.x, d.y, d.verdict, d.rule))
+            .collect()
+    }
+
+    #[test]
+    fn single() {
+        assert_eq!(found("1.\n11"), vec![(0, 1, Verdict::Mine, Rule::Single)]);
+        assert_eq!(found("F1."), vec![(0, 2, Verdict::Safe, Rule::Single)]);
+    }
+
+    #[test]
+    fn subset() {
+        let found = found("...\n121");
+        assert_eq!(found[0], (0, 2, Verdict::Mine, Rule::Subset));
+        let verdicts: Vec<_> = [(0, 0), (0, 1), (0, 2)]
+            .iter()
+            .map(|&(x, y)| found.iter().find(|d| (d.0, d.1) == (x, y)).map(|d| d.2))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![Some(Verdict::Mine), Some(Verdict::Safe), Some(Verdict::Mine)]
+        );
+    }
+
+    #[test]
+    fn mine_count() {
+        assert_eq!(found(".1.."), vec![]);
+        assert_eq!(found("4x1 1\n.1.."), vec![(0, 3, Verdict::Safe, Rule::MineCount)]);
+        assert_eq!(found("4x1 2\n.1.."), vec![(0, 3, Verdict::Mine, Rule::MineCount)]);
+    }
+
+    #[test]
+    fn inconsistent_flags() {
+        // The 1 has two flags, so it says nothing about the cells below.
+        assert_eq!(found("F1F\n..."), vec![]);
+        let probs = probabilities(&puzzle::parse("F1F\n...").unwrap());
+        assert_eq!(probs.get(0, 0), None);
+    }
+
+    fn probs(text: &str) -> Probabilities {
+        probabilities(&puzzle::parse(text).unwrap())
+    }
+
+    fn near(p: Option<f64>, want: f64) -> bool {
+        p.is_some_and(|p| (p - want).abs() < 1e-9)
+    }
+
+    #[test]
+    fn fifty_fifty() {
+        let p = probs("..\n11");
+        assert!(p.exact);
+        assert!(near(p.get(0, 0), 0.5) && near(p.get(0, 1), 0.5));
+        assert_eq!(p.get(1, 0), None);
+    }
+
+    #[test]
+    fn inconsistent_numbers() {
+        // The 8 needs a mine on every side but the header allows one.
+        let p = probs("3x3 1\n...\n.8.\n...");
+        assert!(!p.consistent);
+        assert_eq!(p.get(0, 0), None);
+        assert!(probs("3x3 8\n...\n.8.\n...").consistent);
+    }
+
+    #[test]
+    fn exact_safe_cells() {
+        let p = probs("5x3 2\n.....\n.121.\n.....");
+        assert_eq!(p.safe().len(), 8);
+        assert!(p.safe().contains(&(0, 2)) && !p.safe().contains(&(0, 1)));
+    }
+
+    #[test]
+    fn interior_weighting() {
+        // Either the middle cell is a mine, or both outer ones are; the cells
+        // no number touches decide which is likelier.
+        let board = ".1.1...";
+        let p = probs(board);
+        assert!(near(p.get(0, 2), 0.5));
+        assert_eq!(p.get(0, 5), None);
+
+        let two = probs(&format!("7x1 2\n{}", board));
+        assert!(near(two.get(0, 2), 2.0 / 3.0) && near(two.get(0, 0), 1.0 / 3.0));
+        assert!(near(two.get(0, 5), 1.0 / 3.0));
+
+        let three = probs(&format!("7x1 3\n{}", board));
+        assert!(near(three.get(0, 2), 1.0 / 3.0) && near(three.get(0, 0), 2.0 / 3.0));
+        assert!(near(three.get(0, 6), 2.0 / 3.0));
+    }
+
+    #[test]
+    fn estimate_large_frontier() {
+        // Every 3 sees three mines in six or seven cells, far too many
+        // arrangements to count, so each cell gets the density of the numbers
+        // around it.
+        let cols = 40;
+        let hidden = ".".repeat(cols);
+        let numbers = format!(".{}.", "3".repeat(cols - 2));
+        let p = probs(&format!("{}\n{}\n{}", hidden, numbers, hidden));
+        assert!(!p.exact);
+        assert!(near(p.get(0, 20), 0.5));
+        assert!(near(p.get(1, 0), 3.0 / 7.0));
+    }
+
+    #[test]
+    fn hint_from_exact_zero() {
+        // Every arrangement has one mine in column 2 and one in column 4,
+        // which no single rule or subset shows.
+        let pos = puzzle::parse("5x3 2\n.....\n.121.\n.....").unwrap();
+        assert!(solve(&pos).iter().all(|d| d.verdict != Verdict::Safe));
+        let safe = hint(&pos).unwrap();
+        assert!(safe.safe && safe.deduction.is_none());
+        assert_eq!(safe.probability, 0.0);
+        assert!(safe.explain(&pos).starts_with("no single rule shows it"));
+        let guess = hint(&puzzle::parse(".1.").unwrap()).unwrap();
+        assert!(!guess.safe);
+    }
+}
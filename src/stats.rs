@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::profile;
+use crate::storage;
+
+const STATS_FILE: &str = "stats.json";
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModeStats {
+    pub played: u32,
+    pub won: u32,
+    /// Best winning time in milliseconds.
+    pub best: Option<u64>,
+    /// Sum of all winning times in milliseconds.
+    pub total_time: u64,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub best_bbbv_per_second: f64,
+}
+
+impl ModeStats {
+    pub fn lost(&self) -> u32 {
+        self.played - self.won
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            return 0.0;
+        }
+        self.won as f64 / self.played as f64
+    }
+
+    /// Average winning time in milliseconds.
+    pub fn average(&self) -> Option<u64> {
+        (self.won > 0).then(|| self.total_time / self.won as u64)
+    }
+}
+
+/// Ranked daily challenge results of one difficulty.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyStats {
+    pub played: u32,
+    pub won: u32,
+    /// Last day a ranked attempt was started.
+    pub last_played: Option<i64>,
+    pub last_won: Option<i64>,
+    /// Consecutive days won, up to `last_won`.
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl DailyStats {
+    /// The streak as of `today`, broken if yesterday's board wasn't won.
+    pub fn current_streak(&self, today: i64) -> u32 {
+        match self.last_won {
+            Some(day) if day >= today - 1 => self.streak,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Stats {
+    modes: BTreeMap<String, ModeStats>,
+    #[serde(default)]
+    daily: BTreeMap<String, DailyStats>,
+}
+
+impl Stats {
+    pub fn load(profile: &str) -> Result<Self, StorageError> {
+        storage::load(&profile::file(profile, STATS_FILE))
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), StorageError> {
+        storage::save(&profile::file(profile, STATS_FILE), self)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ModeStats> {
+        self.modes.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &ModeStats)> {
+        self.modes.iter()
+    }
+
+    /// Clear everything but the day of each last ranked daily attempt, so a
+    /// reset can't buy a second ranked try at a board already seen.
+    pub fn reset(&mut self) {
+        self.modes.clear();
+        for entry in self.daily.values_mut() {
+            *entry = DailyStats {
+                last_played: entry.last_played,
+                ..DailyStats::default()
+            };
+        }
+    }
+
+    pub fn daily(&self) -> impl Iterator<Item = (&String, &DailyStats)> {
+        self.daily.iter()
+    }
+
+    /// Mark the daily board as attempted, returning whether this attempt is ranked.
+    pub fn start_daily(&mut self, level: &str, day: i64) -> bool {
+        let entry = self.daily.entry(level.to_string()).or_default();
+        if entry.last_played.is_some_and(|last| last >= day) {
+            return false;
+        }
+        entry.last_played = Some(day);
+        entry.played += 1;
+        true
+    }
+
+    pub fn record_daily(&mut self, level: &str, day: i64, won: bool) {
+        let entry = self.daily.entry(level.to_string()).or_default();
+        if won {
+            entry.won += 1;
+            entry.streak = match entry.last_won {
+                Some(last) if last == day - 1 => entry.streak + 1,
+                _ => 1,
+            };
+            entry.last_won = Some(day);
+            entry.best_streak = entry.best_streak.max(entry.streak);
+        } else {
+            entry.streak = 0;
+        }
+    }
+
+    pub fn record(&mut self, key: &str, won: bool, time: Duration, bbbv_per_second: f64) {
+        let entry = self.modes.entry(key.to_string()).or_default();
+        entry.played += 1;
+        if won {
+            entry.won += 1;
+            let ms = time.as_millis() as u64;
+            if entry.best.is_none_or(|best| ms < best) {
+                entry.best = Some(ms);
+            }
+            entry.total_time += ms;
+            entry.streak += 1;
+            entry.best_streak = entry.best_streak.max(entry.streak);
+            entry.best_bbbv_per_second = entry.best_bbbv_per_second.max(bbbv_per_second);
+        } else {
+            entry.streak = 0;
+        }
+    }
+}
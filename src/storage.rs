@@ -0,0 +1,34 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::StorageError;
+
+/// Directory holding everything the game persists, e.g. `~/.local/share/t-minesweeper`.
+pub fn data_dir() -> Result<PathBuf, StorageError> {
+    let dir = dirs::data_dir().ok_or(StorageError::NoDataDir)?;
+    Ok(dir.join("t-minesweeper"))
+}
+
+/// Load a JSON file from the data directory, falling back to the default value
+/// if it doesn't exist yet.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> Result<T, StorageError> {
+    let path = data_dir()?.join(name);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<(), StorageError> {
+    let path = data_dir()?.join(name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(value)?;
+    fs::write(path, content)?;
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use crate::config::*;
+use crate::error::ReplayError;
+use crate::raw::{self, Event, SQUARE};
+use crate::replay::*;
+
+/// Parse an Arbiter replay (AVF).
+///
+/// After a version byte and four unused bytes comes the level (3 to 5 for
+/// beginner to expert, 6 for custom followed by width - 1, height - 1 and a
+/// two byte mine count), then the mines as one-based (row, column) byte pairs.
+/// A block of text follows, and then the mouse events, eight bytes each:
+///
+/// ```text
+/// code  x_hi  secs_lo  x_lo  hundredths  y_hi  secs_hi  y_lo
+/// ```
+///
+/// Seconds are stored plus one, so the first event is found as the first
+/// record at second zero, and a record at second "minus one" ends the list.
+pub fn parse_avf(data: &[u8]) -> Result<Replay, ReplayError> {
+    let mut reader = Reader { data, pos: 5 };
+    let config = match reader.u8()? {
+        3 => Config::new(8, 8, 10),
+        4 => Config::new(16, 16, 40),
+        5 => Config::new(30, 16, 99),
+        6 => {
+            let col = reader.u8()? as usize + 1;
+            let row = reader.u8()? as usize + 1;
+            Config::new(col, row, reader.u16()? as i32)
+        }
+        _ => return Err(ReplayError::InvalidBoard),
+    };
+    let mut mines = Vec::new();
+    for _ in 0..config.mine {
+        let (x, y) = (reader.u8()? as usize, reader.u8()? as usize);
+        if x == 0 || y == 0 {
+            return Err(ReplayError::InvalidBoard);
+        }
+        mines.push((x - 1, y - 1));
+    }
+    check(&config, &mines)?;
+
+    let first = |r: &[u8]| avf_kind(r[0]).is_some() && r[2] == 1 && r[4] < 100 && r[6] == 0;
+    while !first(reader.peek(8)?) {
+        reader.pos += 1;
+    }
+    let mut events = Vec::new();
+    while let Ok(r) = reader.take(8) {
+        if r[2] == 0 && r[6] == 0 {
+            break;
+        }
+        let Some(kind) = avf_kind(r[0]) else {
+            continue;
+        };
+        let secs = (r[6] as u64 * 256 + r[2] as u64) - 1;
+        let (px, py) = (
+            r[1] as usize * 256 + r[3] as usize,
+            r[5] as usize * 256 + r[7] as usize,
+        );
+        events.push(Event {
+            time: secs * 1000 + r[4] as u64 * 10,
+            kind,
+            x: py / SQUARE + 1,
+            y: px / SQUARE + 1,
+        });
+    }
+
+    Ok(Replay {
+        config,
+        mode: Mode::Imported,
+        seed: 0,
+        moves: raw::moves(config, &mines, events),
+        mines,
+    })
+}
+
+fn avf_kind(code: u8) -> Option<&'static str> {
+    match code {
+        1 => Some("mv"),
+        3 => Some("lc"),
+        5 | 21 => Some("lr"),
+        9 => Some("rc"),
+        17 | 145 => Some("rr"),
+        33 => Some("mc"),
+        65 | 193 => Some("mr"),
+        _ => None,
+    }
+}
+
+/// Where the board starts in a Minesweeper Clone window, in pixels.
+const RMV_LEFT: usize = 12;
+const RMV_TOP: usize = 56;
+
+/// Parse a Minesweeper Clone or Viennasweeper replay (RMV), big-endian
+/// throughout.
+///
+/// The file starts with `*rmv`, a two byte format version of 1 and the sizes
+/// of its sections: result, version and player text, board, preflags and
+/// properties (two bytes each), the events (four bytes) and the checksum.
+/// The board section is four unused bytes, width, height, a two byte mine
+/// count and the mines as zero-based (column, row) pairs; the preflags
+/// section a two byte count and pairs alike. Every event is a type byte, and
+/// type 1 is a mouse event:
+///
+/// ```text
+/// code  time (3, milliseconds)  x (2)  y (2)
+/// ```
+///
+/// Types 5 to 7 end the game. The length of any other event type is not
+/// known, so a replay holding one is refused rather than read out of step.
+pub fn parse_rmv(data: &[u8]) -> Result<Replay, ReplayError> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.take(4)? != b"*rmv" || reader.u16()? != 1 {
+        return Err(ReplayError::NotA("RMV"));
+    }
+    let text = (0..3)
+        .map(|_| reader.u16().map(usize::from))
+        .sum::<Result<usize, _>>()?;
+    let board = reader.u16()? as usize;
+    let preflags = reader.u16()? as usize;
+    let properties = reader.u16()? as usize;
+    let video = reader.u32()? as usize;
+    reader.u16()?;
+    reader.take(text)?;
+
+    let mut section = Reader {
+        data: reader.take(board)?,
+        pos: 4,
+    };
+    let col = section.u8()? as usize;
+    let row = section.u8()? as usize;
+    let mine = section.u16()?;
+    let mut mines = Vec::new();
+    for _ in 0..mine {
+        let (y, x) = (section.u8()? as usize, section.u8()? as usize);
+        mines.push((x, y));
+    }
+    let config = Config::new(col, row, mine as i32);
+    check(&config, &mines)?;
+
+    let mut events = Vec::new();
+    let mut section = Reader {
+        data: reader.take(preflags)?,
+        pos: 0,
+    };
+    if preflags > 0 {
+        for _ in 0..section.u16()? {
+            let (y, x) = (section.u8()? as usize + 1, section.u8()? as usize + 1);
+            events.push(Event {
+                time: 0,
+                kind: "rc",
+                x,
+                y,
+            });
+            events.push(Event {
+                time: 0,
+                kind: "rr",
+                x,
+                y,
+            });
+        }
+    }
+    reader.take(properties)?;
+
+    let mut section = Reader {
+        data: reader.take(video)?,
+        pos: 0,
+    };
+    while section.pos < section.data.len() {
+        match section.u8()? {
+            1 => {
+                let kind = match section.u8()? {
+                    1 => "mv",
+                    2 => "lc",
+                    3 => "lr",
+                    4 => "rc",
+                    5 => "rr",
+                    6 => "mc",
+                    7 => "mr",
+                    _ => "",
+                };
+                let time = section.take(3)?.iter().fold(0, |t, &b| t << 8 | b as u64);
+                let (px, py) = (section.u16()? as usize, section.u16()? as usize);
+                let (Some(px), Some(py)) = (px.checked_sub(RMV_LEFT), py.checked_sub(RMV_TOP))
+                else {
+                    continue;
+                };
+                events.push(Event {
+                    time,
+                    kind,
+                    x: py / SQUARE + 1,
+                    y: px / SQUARE + 1,
+                });
+            }
+            5..=7 => break,
+            kind => return Err(ReplayError::InvalidEvent(kind, section.pos - 1)),
+        }
+    }
+
+    Ok(Replay {
+        config,
+        mode: Mode::Imported,
+        seed: 0,
+        moves: raw::moves(config, &mines, events),
+        mines,
+    })
+}
+
+/// A board with squares, every mine on it and no square mined twice.
+fn check(config: &Config, mines: &[(usize, usize)]) -> Result<(), ReplayError> {
+    if config.col == 0 || config.row == 0 {
+        return Err(ReplayError::InvalidBoard);
+    }
+    let mut seen = vec![vec![false; config.col]; config.row];
+    for &(x, y) in mines {
+        if x >= config.row || y >= config.col || seen[x][y] {
+            return Err(ReplayError::InvalidBoard);
+        }
+        seen[x][y] = true;
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self, n: usize) -> Result<&'a [u8], ReplayError> {
+        self.data
+            .get(self.pos..self.pos + n)
+            .ok_or(ReplayError::Truncated)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ReplayError> {
+        let bytes = self.peek(n)?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Action;
+
+    /// An AVF or RMV event pressing and releasing the left button over square
+    /// (2, 2), half a second in.
+    fn click(event: impl Fn(u8, u16) -> Vec<u8>, press: u8, release: u8) -> Vec<u8> {
+        [event(press, 500), event(release, 500)].concat()
+    }
+
+    fn check_click(replay: &Replay) {
+        assert_eq!((replay.config.col, replay.config.row), (3, 3));
+        assert_eq!(replay.mines, vec![(0, 0)]);
+        let moves: Vec<_> = replay
+            .moves
+            .iter()
+            .map(|mv| (mv.action, mv.x, mv.y, mv.time))
+            .collect();
+        assert!(moves == vec![(Action::Reveal, 2, 2, 500)]);
+    }
+
+    #[test]
+    fn avf() {
+        let mut data = vec![0, 0, 0, 0, 0, 6, 2, 2, 0, 1, 1, 1];
+        data.extend(b"[0|19.10.2026|1760000000|B1T1.00]");
+        let event = |code, ms: u16| {
+            let secs = ms / 1000 + 1;
+            vec![
+                code,
+                0,
+                secs as u8,
+                40,
+                (ms % 1000 / 10) as u8,
+                0,
+                (secs >> 8) as u8,
+                40,
+            ]
+        };
+        data.extend(click(event, 3, 5));
+        data.extend([0; 8]);
+        check_click(&parse_avf(&data).unwrap());
+    }
+
+    #[test]
+    fn rmv() {
+        let board = [0, 0, 0, 0, 3, 3, 0, 1, 0, 0];
+        let event = |code, ms: u16| {
+            let (x, y) = (RMV_LEFT as u16 + 40, RMV_TOP as u16 + 40);
+            [
+                [1, code, 0].as_slice(),
+                &ms.to_be_bytes(),
+                &x.to_be_bytes(),
+                &y.to_be_bytes(),
+            ]
+            .concat()
+        };
+        let video = click(event, 2, 3);
+
+        let mut data = b"*rmv".to_vec();
+        for size in [1, 0, 0, 0, board.len() as u16, 0, 0] {
+            data.extend(size.to_be_bytes());
+        }
+        data.extend((video.len() as u32).to_be_bytes());
+        data.extend(0u16.to_be_bytes());
+        data.extend(board);
+        data.extend(video);
+        check_click(&parse_rmv(&data).unwrap());
+    }
+
+    #[test]
+    fn rmv_magic() {
+        assert!(matches!(
+            parse_rmv(b"*avf\0\x01"),
+            Err(ReplayError::NotA(_))
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Instant;
+
+use crate::app::*;
+use crate::game::*;
+use crate::replay::*;
+
+const SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// Playback state of a replay shown on the board.
+pub struct Viewer {
+    pub replay: Replay,
+    pub game: Game,
+    /// Number of moves applied to `game`.
+    pub pos: usize,
+    /// Current replay time in milliseconds.
+    pub clock: u64,
+    pub playing: bool,
+    speed: usize,
+    last_tick: Instant,
+}
+
+impl Viewer {
+    pub fn new(replay: Replay) -> Self {
+        let game = replay.to_game();
+        Self {
+            replay,
+            game,
+            pos: 0,
+            clock: 0,
+            playing: false,
+            speed: 2,
+            last_tick: Instant::now(),
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn toggle(&mut self) {
+        if !self.playing && self.pos == self.replay.moves.len() {
+            self.seek(0);
+        }
+        self.playing = !self.playing;
+        self.last_tick = Instant::now();
+    }
+
+    /// Advance the clock by the real time passed since the last tick.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        if self.playing {
+            let passed = (now - self.last_tick).as_millis() as f64 * self.speed();
+            self.clock = (self.clock + passed as u64).min(self.replay.duration());
+            self.advance();
+            if self.pos == self.replay.moves.len() {
+                self.playing = false;
+            }
+        }
+        self.last_tick = now;
+    }
+
+    pub fn step_forward(&mut self) {
+        if let Some(mv) = self.replay.moves.get(self.pos) {
+            self.clock = mv.time;
+            self.advance();
+        }
+    }
+
+    pub fn step_back(&mut self) {
+        if self.pos == 0 {
+            return;
+        }
+        let pos = self.pos - 1;
+        self.rebuild(pos);
+        self.clock = pos.checked_sub(1).map_or(0, |i| self.replay.moves[i].time);
+    }
+
+    /// Jump to the given time in milliseconds.
+    pub fn seek(&mut self, time: u64) {
+        self.rebuild(0);
+        self.clock = time.min(self.replay.duration());
+        self.advance();
+    }
+
+    /// Reset the board and apply the first `pos` moves.
+    fn rebuild(&mut self, pos: usize) {
+        let mut status = Status::Replay;
+        self.game = self.replay.to_game();
+        for &mv in &self.replay.moves[..pos] {
+            let _ = self.game.replay(mv, &mut status);
+        }
+        self.pos = pos;
+    }
+
+    /// Apply every move up to the clock.
+    fn advance(&mut self) {
+        let mut status = Status::Replay;
+        while let Some(&mv) = self.replay.moves.get(self.pos) {
+            if mv.time > self.clock {
+                break;
+            }
+            let _ = self.game.replay(mv, &mut status);
+            self.pos += 1;
+        }
+    }
+}